@@ -0,0 +1,45 @@
+#![no_main]
+#![no_std]
+
+use dk::{
+    gpiote::{self, Event},
+    peripheral::GPIOTE,
+};
+use panic_log as _; // panic handler
+
+#[rtic::app(device = dk, peripherals = true)]
+const APP: () = {
+    struct Resources {
+        gpiote: GPIOTE,
+    }
+
+    #[init]
+    fn init(cx: init::Context) -> init::LateResources {
+        let board = dk::init_rtic(cx.core, cx.device);
+
+        gpiote::init(&board.gpiote);
+
+        log::info!("GPIOTE initialized; press any of the buttons");
+
+        init::LateResources {
+            gpiote: board.gpiote,
+        }
+    }
+
+    #[task(binds = GPIOTE, resources = [gpiote])]
+    fn main(cx: main::Context) {
+        let gpiote = cx.resources.gpiote;
+
+        while let Some(event) = gpiote::next_event(gpiote) {
+            on_event(event)
+        }
+    }
+};
+
+fn on_event(event: Event) {
+    log::info!("GPIOTE: {:?} @ {:?}", event, dk::uptime());
+
+    if event == Event::Button4Pressed {
+        dk::exit()
+    }
+}
@@ -0,0 +1,43 @@
+#![no_main]
+#![no_std]
+
+use core::time::Duration;
+
+use cortex_m::asm;
+use cortex_m_rt::entry;
+use panic_log as _; // panic handler
+
+#[entry]
+fn main() -> ! {
+    let board = dk::init().unwrap();
+    let mut leds = board.leds;
+    let mut timer = board.timer;
+
+    if board.watchdog.caused_last_reset() {
+        // NOTE `probe-run` will likely not report this message as it loses track of the program
+        // after a watchdog reset; look at the LEDs instead
+        log::info!("recovered from a watchdog reset");
+        leds._4.on();
+        loop {
+            asm::wfi()
+        }
+    }
+
+    let mut watchdog = board.watchdog.start(Duration::from_secs(1));
+
+    // a healthy main loop: the watchdog is fed on every iteration
+    for _ in 0..10 {
+        leds._1.toggle();
+        timer.wait(Duration::from_millis(250));
+        watchdog.feed();
+    }
+
+    // simulate a bug that makes the program hang
+    log::info!("hanging; the watchdog will reset the device in 1 second");
+    leds._1.off();
+    leds._2.on();
+    loop {
+        // TODO try feeding the watchdog here
+        continue;
+    }
+}
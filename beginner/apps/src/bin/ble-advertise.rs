@@ -0,0 +1,25 @@
+#![no_main]
+#![no_std]
+
+use core::time::Duration;
+
+use cortex_m_rt::entry;
+use dk::ble::Advertiser;
+use panic_log as _; // the panicking behavior
+
+#[entry]
+fn main() -> ! {
+    let board = dk::init().unwrap();
+    let mut timer = board.timer;
+
+    // NOTE pick a name that you'll recognize in your phone's scanner app
+    let mut advertiser = Advertiser::new(board.radio);
+    advertiser.set_name("nRF52840-DK");
+
+    log::info!("advertising as {:02x?}", advertiser.address());
+
+    loop {
+        advertiser.advertise();
+        timer.wait(Duration::from_millis(100));
+    }
+}
@@ -0,0 +1,37 @@
+#![no_main]
+#![no_std]
+
+use cortex_m_rt::entry;
+use dk::{ecb::Aes128, sha256};
+use panic_log as _; // panic handler
+
+#[entry]
+fn main() -> ! {
+    let board = dk::init().unwrap();
+
+    // test vector from appendix C.1 of FIPS-197
+    let key = [
+        0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e,
+        0x0f,
+    ];
+    let mut block = [
+        0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd, 0xee,
+        0xff,
+    ];
+
+    let mut aes = Aes128::new(board.unused.ECB, &key);
+    aes.encrypt_block(&mut block);
+    log::info!("AES-128: {:02x?}", block);
+    assert_eq!(
+        block,
+        [
+            0x69, 0xc4, 0xe0, 0xd8, 0x6a, 0x7b, 0x04, 0x30, 0xd8, 0xcd, 0xb7, 0x80, 0x70, 0xb4,
+            0xc5, 0x5a,
+        ]
+    );
+
+    let digest = sha256::digest(b"abc");
+    log::info!("SHA-256: {:02x?}", digest);
+
+    dk::exit()
+}
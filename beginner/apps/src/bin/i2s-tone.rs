@@ -0,0 +1,47 @@
+#![no_main]
+#![no_std]
+
+use cortex_m_rt::entry;
+use dk::{
+    gpio::Level,
+    i2s::{self, I2s},
+};
+use panic_log as _; // panic handler
+
+// frequency of the tone, in Hz
+const FREQUENCY: u32 = 440;
+// volume; maximum is `i16::MAX`
+const AMPLITUDE: i16 = 4_000;
+
+#[entry]
+fn main() -> ! {
+    let board = dk::init().unwrap();
+
+    // I2S DAC / amplifier breakout connected to P0.28 (SCK), P0.29 (LRCK) and P0.30 (SDOUT)
+    let pins = board.pins;
+    let sck = pins.p0_28.into_push_pull_output(Level::Low).degrade();
+    let lrck = pins.p0_29.into_push_pull_output(Level::Low).degrade();
+    let sdout = pins.p0_30.into_push_pull_output(Level::Low).degrade();
+
+    let mut i2s = I2s::new(board.unused.I2S, sck, lrck, sdout, None);
+    i2s.start();
+
+    // play a square wave for about 2 seconds
+    let half_period = i2s::SAMPLE_RATE / FREQUENCY / 2;
+    let mut n = 0;
+    for _ in 0..(2 * i2s::SAMPLE_RATE as usize / i2s::BUFFER_LEN) {
+        for frame in i2s.next_buffer().iter_mut() {
+            let sample = if (n / half_period) % 2 == 0 {
+                AMPLITUDE
+            } else {
+                -AMPLITUDE
+            };
+            *frame = [sample, sample];
+            n += 1;
+        }
+    }
+
+    i2s.stop();
+
+    dk::exit()
+}
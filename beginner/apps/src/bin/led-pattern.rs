@@ -0,0 +1,26 @@
+#![no_main]
+#![no_std]
+
+use core::time::Duration;
+
+use cortex_m_rt::entry;
+use dk::pattern;
+use panic_log as _; // panic handler
+
+#[entry]
+fn main() -> ! {
+    let board = dk::init().unwrap();
+
+    let mut leds = board.leds;
+    let mut timer = board.timer;
+
+    // one LED after the other, then all of them
+    let spin = pattern::parse("1 2 4 3 1 2 4 3 1234 _ 1234", Duration::from_millis(250));
+    leds.play(spin, &mut timer);
+
+    // "SOS" on LED1
+    let dot = Duration::from_millis(150);
+    leds.play(pattern::morse("SOS", 0b0001, dot), &mut timer);
+
+    dk::exit()
+}
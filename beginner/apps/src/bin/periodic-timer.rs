@@ -0,0 +1,30 @@
+#![no_main]
+#![no_std]
+
+use core::time::Duration;
+
+use cortex_m_rt::entry;
+use panic_log as _; // panic handler
+
+#[entry]
+fn main() -> ! {
+    let board = dk::init().unwrap();
+
+    let timer = board
+        .timer
+        .start_periodic(Duration::from_millis(500), on_tick);
+
+    // the main loop keeps counting; every 500 ms the timer interrupt preempts it to run `on_tick`
+    let mut count = 0u32;
+    while timer.ticks() < 10 {
+        count = count.wrapping_add(1);
+    }
+
+    log::info!("the main loop ran {} iterations", count);
+
+    dk::exit()
+}
+
+fn on_tick() {
+    log::info!("tick @ {:?}", dk::uptime());
+}
@@ -0,0 +1,50 @@
+#![no_main]
+#![no_std]
+
+use cortex_m_rt::entry;
+use dk::ppi::{Event, Ppi, Task};
+use panic_log as _; // panic handler
+
+// 1 ms at 16 MHz
+const COMPARE: u32 = 16_000;
+
+#[entry]
+fn main() -> ! {
+    let board = dk::init().unwrap();
+
+    // TIMER1 counts at 16 MHz: one tick = 62.5 ns
+    let timer = board.unused.TIMER1;
+    timer.mode.write(|w| w.mode().timer());
+    timer.bitmode.write(|w| w.bitmode()._32bit());
+    timer.prescaler.write(|w| unsafe { w.prescaler().bits(0) });
+    timer.cc[0].write(|w| unsafe { w.bits(COMPARE) });
+
+    // 1. the hardware captures the timer value when the compare event fires
+    let mut ppi = Ppi::new(board.unused.PPI);
+    let channel = ppi.connect(
+        Event::new(&timer.events_compare[0]),
+        Task::new(&timer.tasks_capture[1]),
+    );
+    ppi.enable(&channel);
+
+    timer.tasks_clear.write(|w| unsafe { w.bits(1) });
+    timer.tasks_start.write(|w| unsafe { w.bits(1) });
+    while timer.events_compare[0].read().bits() == 0 {
+        continue;
+    }
+    let hardware = timer.cc[1].read().bits() - COMPARE;
+
+    // 2. the CPU polls the compare event and then captures the timer value
+    ppi.disconnect(channel);
+    timer.events_compare[0].reset();
+    timer.tasks_clear.write(|w| unsafe { w.bits(1) });
+    while timer.events_compare[0].read().bits() == 0 {
+        continue;
+    }
+    timer.tasks_capture[1].write(|w| unsafe { w.bits(1) });
+    let cpu = timer.cc[1].read().bits() - COMPARE;
+
+    log::info!("latency: PPI = {} ticks, CPU = {} ticks", hardware, cpu);
+
+    dk::exit()
+}
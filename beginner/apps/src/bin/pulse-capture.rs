@@ -0,0 +1,33 @@
+#![no_main]
+#![no_std]
+
+use core::time::Duration;
+
+use cortex_m_rt::entry;
+use dk::{capture::Capture, ppi::Ppi};
+use panic_log as _; // panic handler
+
+#[entry]
+fn main() -> ! {
+    let board = dk::init().unwrap();
+
+    // signal to measure connected to P0.04
+    let pin = board.pins.p0_04.degrade();
+    let gpiote = board.gpiote;
+    let mut ppi = Ppi::new(board.unused.PPI);
+    let mut capture = Capture::new(board.unused.TIMER1, &gpiote, &mut ppi, pin);
+
+    let timeout = Duration::from_secs(1);
+    for _ in 0..10 {
+        match capture.measure_pulse(&gpiote, timeout) {
+            Some(width) => log::info!("pulse width: {:?}", width),
+            None => log::info!("no pulse"),
+        }
+    }
+
+    if let Some(period) = capture.measure_period(&gpiote, timeout) {
+        log::info!("period: {:?}", period);
+    }
+
+    dk::exit()
+}
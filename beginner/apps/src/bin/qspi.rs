@@ -0,0 +1,42 @@
+#![no_main]
+#![no_std]
+
+use cortex_m_rt::entry;
+use dk::qspi::Qspi;
+use panic_log as _; // panic handler
+
+// EasyDMA buffers used with the QSPI peripheral must be 4-byte aligned
+#[repr(align(4))]
+struct Aligned([u8; 16]);
+
+#[entry]
+fn main() -> ! {
+    let board = dk::init().unwrap();
+
+    let pins = board.pins;
+    let mut flash = Qspi::new(
+        board.unused.QSPI,
+        pins.p0_17,
+        pins.p0_19,
+        pins.p0_20,
+        pins.p0_21,
+        pins.p0_22,
+        pins.p0_23,
+    );
+
+    let address = 0;
+    flash.erase_sector(address);
+
+    let data = Aligned(*b"Hello, QSPI! :-)");
+    flash.program(address, &data.0);
+
+    let mut buffer = Aligned([0; 16]);
+    flash.read(address, &mut buffer.0);
+    log::info!("read: {:?}", core::str::from_utf8(&buffer.0));
+    assert_eq!(buffer.0, data.0);
+
+    // the same data can be read through the memory mapped (XIP) region
+    assert_eq!(&flash.xip()[..16], &data.0[..]);
+
+    dk::exit()
+}
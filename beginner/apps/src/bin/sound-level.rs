@@ -0,0 +1,33 @@
+#![no_main]
+#![no_std]
+
+use cortex_m_rt::entry;
+use dk::{gpio::Level, pdm::Pdm};
+use panic_log as _; // panic handler
+
+#[entry]
+fn main() -> ! {
+    let board = dk::init().unwrap();
+
+    // PDM microphone breakout connected to P0.03 (CLK) and P0.04 (DIN)
+    let pins = board.pins;
+    let clk = pins.p0_03.into_push_pull_output(Level::Low).degrade();
+    let din = pins.p0_04.degrade();
+
+    let mut pdm = Pdm::new(board.unused.PDM, clk, din);
+    pdm.start();
+
+    for _ in 0..100 {
+        let samples = pdm.read();
+        let peak = samples
+            .iter()
+            .map(|s| s.saturating_abs())
+            .max()
+            .unwrap_or(0);
+        log::info!("peak level: {}", peak);
+    }
+
+    pdm.stop();
+
+    dk::exit()
+}
@@ -0,0 +1,141 @@
+//! SAADC (Successive Approximation Analog-to-Digital Converter) peripheral
+
+use core::sync::atomic::{self, Ordering};
+
+use hal::target::SAADC;
+
+/// Analog inputs that can be sampled by the `Adc`
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Input {
+    /// AIN0: pin P0.02
+    Ain0,
+    /// AIN1: pin P0.03
+    Ain1,
+    /// AIN2: pin P0.04
+    Ain2,
+    /// AIN3: pin P0.05
+    Ain3,
+    /// AIN4: pin P0.28
+    Ain4,
+    /// AIN5: pin P0.29
+    Ain5,
+    /// AIN6: pin P0.30
+    Ain6,
+    /// AIN7: pin P0.31
+    Ain7,
+    /// The supply voltage (VDD) of the nRF52840
+    Vdd,
+}
+
+/// Analog-to-Digital Converter
+///
+/// Samples are 12-bit values where 0 corresponds to 0 volts and 4095 corresponds to 3.6 volts. Note
+/// that the nRF52840 pins must not be exposed to voltages higher than VDD (3.0 volts on the DK)
+///
+/// The analog inputs are also handed out as GPIO in the `Pins` of the `Board`; leave the pins that
+/// are sampled as floating inputs (their initial state) and don't use them as GPIO
+pub struct Adc {
+    saadc: SAADC,
+}
+
+// full scale input range = reference voltage (0.6 V) / gain (1/6)
+const FULL_SCALE_MILLIVOLTS: u32 = 3_600;
+// 12-bit resolution
+const MAX_SAMPLE: u16 = (1 << 12) - 1;
+
+impl Adc {
+    /// Enables and calibrates the SAADC peripheral
+    ///
+    /// The SAADC peripheral can be obtained from the `unused` field of the `Board`. The calibration
+    /// takes a few hundred microseconds
+    pub fn new(saadc: SAADC) -> Self {
+        saadc.enable.write(|w| w.enable().enabled());
+        saadc.resolution.write(|w| w.val()._12bit());
+        saadc.oversample.write(|w| w.oversample().bypass());
+        saadc.samplerate.write(|w| w.mode().task());
+
+        // single-ended measurement against the internal reference
+        saadc.ch[0].config.write(|w| {
+            w.refsel().internal();
+            w.gain().gain1_6();
+            w.tacq()._10us();
+            w.mode().se();
+            w.resp().bypass();
+            w.resn().bypass();
+            w.burst().disabled()
+        });
+        saadc.ch[0].pseln.write(|w| w.pseln().nc());
+
+        // calibrate the offset once; this takes a few hundred microseconds
+        saadc
+            .tasks_calibrateoffset
+            .write(|w| w.tasks_calibrateoffset().set_bit());
+        while saadc.events_calibratedone.read().bits() == 0 {
+            continue;
+        }
+        saadc.events_calibratedone.reset();
+
+        log::debug!("SAADC calibrated");
+
+        Self { saadc }
+    }
+
+    /// Samples the given analog `input` and returns the raw 12-bit conversion result
+    pub fn read(&mut self, input: Input) -> u16 {
+        let saadc = &self.saadc;
+        saadc.ch[0].pselp.write(|w| match input {
+            Input::Ain0 => w.pselp().analog_input0(),
+            Input::Ain1 => w.pselp().analog_input1(),
+            Input::Ain2 => w.pselp().analog_input2(),
+            Input::Ain3 => w.pselp().analog_input3(),
+            Input::Ain4 => w.pselp().analog_input4(),
+            Input::Ain5 => w.pselp().analog_input5(),
+            Input::Ain6 => w.pselp().analog_input6(),
+            Input::Ain7 => w.pselp().analog_input7(),
+            Input::Vdd => w.pselp().vdd(),
+        });
+
+        // the conversion result is written to `sample` by the peripheral using DMA
+        let mut sample: i16 = 0;
+        saadc
+            .result
+            .ptr
+            .write(|w| unsafe { w.ptr().bits(&mut sample as *mut i16 as u32) });
+        saadc.result.maxcnt.write(|w| unsafe { w.maxcnt().bits(1) });
+
+        atomic::compiler_fence(Ordering::Release);
+        saadc.tasks_start.write(|w| w.tasks_start().set_bit());
+        saadc.tasks_sample.write(|w| w.tasks_sample().set_bit());
+        while saadc.events_end.read().bits() == 0 {
+            continue;
+        }
+        saadc.events_end.reset();
+        saadc.events_started.reset();
+        atomic::compiler_fence(Ordering::Acquire);
+
+        // NOTE due to noise and offset a single-ended measurement of 0 volts may come out slightly
+        // negative
+        let sample = (sample.max(0) as u16).min(MAX_SAMPLE);
+
+        log::trace!("ADC: {:?} = {}", input, sample);
+
+        sample
+    }
+
+    /// Samples the given analog `input` and returns its voltage in millivolts
+    pub fn read_millivolts(&mut self, input: Input) -> u32 {
+        u32::from(self.read(input)) * FULL_SCALE_MILLIVOLTS / u32::from(MAX_SAMPLE)
+    }
+
+    /// Measures the supply voltage (VDD) and returns it in millivolts
+    pub fn vdd(&mut self) -> u32 {
+        self.read_millivolts(Input::Vdd)
+    }
+
+    /// Disables and releases the SAADC peripheral
+    pub fn free(self) -> SAADC {
+        self.saadc.enable.write(|w| w.enable().disabled());
+        self.saadc
+    }
+}
@@ -0,0 +1,196 @@
+//! Bluetooth Low Energy (BLE) advertising
+//!
+//! This module turns the radio into a BLE *broadcaster*: it sends non-connectable advertisements
+//! on the three advertising channels (37, 38 and 39) using the 1 Mbit/s PHY. These advertisements
+//! show up in BLE scanner apps like nRF Connect.
+
+use core::sync::atomic::{self, Ordering};
+
+use hal::target::{FICR, RADIO};
+
+use crate::radio::Radio;
+
+/// Maximum size of the advertising data, in bytes
+pub const MAX_DATA_LEN: usize = 31;
+
+// Access address used by all advertising packets
+const ACCESS_ADDRESS: u32 = 0x8E89_BED6;
+// CRC initial value used by all advertising packets
+const CRC_INIT: u32 = 0x55_5555;
+// x**24 + x**10 + x**9 + x**6 + x**4 + x**3 + x + 1
+const CRC_POLY: u32 = 0x00_065B;
+
+// (channel index, frequency offset from 2400 MHz)
+const ADVERTISING_CHANNELS: [(u8, u8); 3] = [(37, 2), (38, 26), (39, 80)];
+
+// PDU type: non-connectable undirected advertising
+const ADV_NONCONN_IND: u8 = 0b0010;
+// `TxAdd` bit: the advertiser address is random
+const TX_ADD_RANDOM: u8 = 1 << 6;
+
+// size of the advertiser address (`AdvA`)
+const ADDRESS_LEN: usize = 6;
+// header (2 bytes) + `AdvA` + `AdvData`
+const PDU_LEN: usize = 2 + ADDRESS_LEN + MAX_DATA_LEN;
+
+// AD types
+const AD_FLAGS: u8 = 0x01;
+const AD_COMPLETE_LOCAL_NAME: u8 = 0x09;
+// LE General Discoverable Mode, BR/EDR Not Supported
+const FLAGS: u8 = 0x06;
+
+/// BLE advertiser
+pub struct Advertiser {
+    address: [u8; ADDRESS_LEN],
+    pdu: [u8; PDU_LEN],
+    radio: RADIO,
+}
+
+impl Advertiser {
+    /// Turns the IEEE 802.15.4 `radio` into a BLE advertiser
+    ///
+    /// The advertiser uses the *random static* device address programmed in the chip (FICR) and
+    /// the TX power that was last configured with `set_txpower`. Advertising data is initially
+    /// empty; use `set_name` or `set_data` to fill it
+    pub fn new(radio: Radio) -> Self {
+        let radio = radio.into_registers();
+
+        radio.mode.write(|w| w.mode().ble_1mbit());
+        // NOTE(unsafe) the radio is disabled
+        unsafe {
+            radio.pcnf0.write(|w| {
+                w.lflen()
+                    .bits(8) // 8-bit LENGTH field
+                    .s0len()
+                    .set_bit() // 1-byte S0 field: the first byte of the PDU header
+                    .s1len()
+                    .bits(0) // no S1
+                    .s1incl()
+                    .clear_bit()
+                    .cilen()
+                    .bits(0)
+                    .plen()
+                    ._8bit() // 1-byte preamble
+                    .crcinc()
+                    .exclude() // LENGTH does not account for the CRC
+            });
+            radio.pcnf1.write(|w| {
+                w.maxlen()
+                    .bits((PDU_LEN - 2) as u8) // payload length
+                    .statlen()
+                    .bits(0)
+                    .balen()
+                    .bits(3) // 3-byte base address + 1-byte prefix = 4-byte access address
+                    .endian()
+                    .little()
+                    .whiteen()
+                    .enabled()
+            });
+
+            radio.base0.write(|w| w.base0().bits(ACCESS_ADDRESS << 8));
+            radio
+                .prefix0
+                .write(|w| w.ap0().bits((ACCESS_ADDRESS >> 24) as u8));
+            radio.txaddress.write(|w| w.txaddress().bits(0));
+
+            radio.crccnf.write(|w| w.len().three().skipaddr().skip());
+            radio.crcpoly.write(|w| w.crcpoly().bits(CRC_POLY));
+            radio.crcinit.write(|w| w.crcinit().bits(CRC_INIT));
+        }
+
+        // transmit as soon as the radio is ready and disable it once the packet has been sent
+        radio
+            .shorts
+            .write(|w| w.ready_start().enabled().end_disable().enabled());
+
+        // NOTE(unsafe) FICR is read-only
+        let ficr = unsafe { &*FICR::ptr() };
+        let low = ficr.deviceaddr[0].read().bits().to_le_bytes();
+        let high = ficr.deviceaddr[1].read().bits().to_le_bytes();
+        let mut address = [low[0], low[1], low[2], low[3], high[0], high[1]];
+        // the two most significant bits of a random static address must be set
+        address[5] |= 0b1100_0000;
+
+        let mut advertiser = Self {
+            address,
+            pdu: [0; PDU_LEN],
+            radio,
+        };
+        advertiser.set_data(&[]);
+        advertiser
+    }
+
+    /// Returns the device address, least significant byte first
+    pub fn address(&self) -> [u8; ADDRESS_LEN] {
+        self.address
+    }
+
+    /// Advertises the given `name`
+    ///
+    /// This sets the advertising data to the "Flags" and "Complete Local Name" AD structures
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `name` is longer than 26 bytes
+    pub fn set_name(&mut self, name: &str) {
+        let name = name.as_bytes();
+        // the "Flags" (3 bytes) and the header of the name (2 bytes) must fit as well
+        assert!(name.len() <= MAX_DATA_LEN - 5, "name is too long");
+
+        let mut data = [0; MAX_DATA_LEN];
+        data[..3].copy_from_slice(&[2, AD_FLAGS, FLAGS]);
+        data[3] = name.len() as u8 + 1;
+        data[4] = AD_COMPLETE_LOCAL_NAME;
+        data[5..5 + name.len()].copy_from_slice(name);
+        self.set_data(&data[..5 + name.len()]);
+    }
+
+    /// Sets the advertising data
+    ///
+    /// `data` must be a sequence of AD structures (length, AD type, AD data) as specified in the
+    /// Bluetooth Core Specification Supplement
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `data` is longer than `MAX_DATA_LEN` bytes
+    pub fn set_data(&mut self, data: &[u8]) {
+        assert!(data.len() <= MAX_DATA_LEN, "advertising data is too long");
+
+        self.pdu[0] = ADV_NONCONN_IND | TX_ADD_RANDOM;
+        self.pdu[1] = (ADDRESS_LEN + data.len()) as u8;
+        self.pdu[2..2 + ADDRESS_LEN].copy_from_slice(&self.address);
+        self.pdu[2 + ADDRESS_LEN..2 + ADDRESS_LEN + data.len()].copy_from_slice(data);
+    }
+
+    /// Performs one advertising event: sends the advertisement once on each advertising channel
+    ///
+    /// Call this periodically, e.g. every 100 ms, to stay visible to scanners
+    pub fn advertise(&mut self) {
+        for &(index, frequency) in ADVERTISING_CHANNELS.iter() {
+            // NOTE(unsafe) the radio is disabled between transmissions; the PDU is in RAM
+            unsafe {
+                self.radio
+                    .frequency
+                    .write(|w| w.frequency().bits(frequency).map().default());
+                self.radio
+                    .datawhiteiv
+                    .write(|w| w.datawhiteiv().bits(index));
+                self.radio
+                    .packetptr
+                    .write(|w| w.packetptr().bits(self.pdu.as_ptr() as u32));
+            }
+
+            // the radio will read the PDU from RAM using DMA
+            atomic::compiler_fence(Ordering::Release);
+            self.radio.tasks_txen.write(|w| w.tasks_txen().set_bit());
+            while self.radio.events_disabled.read().bits() == 0 {}
+            atomic::compiler_fence(Ordering::Acquire);
+
+            self.radio.events_ready.reset();
+            self.radio.events_end.reset();
+            self.radio.events_disabled.reset();
+        }
+
+        log::trace!("ble: advertised on channels 37, 38 and 39");
+    }
+}
@@ -0,0 +1,149 @@
+//! Pulse width and period measurement
+//!
+//! The edges of the measured signal are timestamped by the hardware: GPIOTE events trigger, through
+//! PPI, the capture tasks of a TIMER
+
+use core::time::Duration;
+
+use embedded_hal::digital::v2::InputPin;
+use hal::{
+    gpio::{Floating, Input, Pin},
+    target::TIMER1,
+};
+
+use crate::{
+    peripheral::GPIOTE,
+    ppi::{Channel, Event, Ppi, Task},
+};
+
+// GPIOTE channel used to detect the edges of the signal; channels 0 to 3 are used by
+// `gpiote::init`
+const CHANNEL: usize = 4;
+
+/// Measures the pulses of a digital signal with a resolution of 1 microsecond
+///
+/// Uses the TIMER1 peripheral, GPIOTE channel 4 and one PPI channel. Both edges of the signal are
+/// timestamped into the same capture register so the direction of an edge is determined by
+/// reading the level of the pin after the edge; pulses must be longer than a few microseconds to
+/// be measured correctly
+pub struct Capture {
+    timer: TIMER1,
+    pin: Pin<Input<Floating>>,
+    channel: Channel,
+}
+
+impl Capture {
+    /// Starts monitoring the signal on `pin`
+    ///
+    /// The TIMER1 and PPI peripherals can be obtained from the `unused` field of the `Board`
+    pub fn new(timer: TIMER1, gpiote: &GPIOTE, ppi: &mut Ppi, pin: Pin<Input<Floating>>) -> Self {
+        // 16 MHz / 2^4 = 1 MHz
+        timer.mode.write(|w| w.mode().timer());
+        timer.bitmode.write(|w| w.bitmode()._32bit());
+        timer.prescaler.write(|w| unsafe { w.prescaler().bits(4) });
+
+        gpiote.config[CHANNEL].write(|w| {
+            w.mode().event();
+            unsafe { w.psel().bits(pin.pin) };
+            w.port().bit(pin.port);
+            w.polarity().toggle()
+        });
+
+        // CC[0] holds the time of the last edge
+        let channel = ppi.connect(
+            Event::new(&gpiote.events_in[CHANNEL]),
+            Task::new(&timer.tasks_capture[0]),
+        );
+        ppi.enable(&channel);
+
+        log::debug!(
+            "capture: monitoring P{}.{}",
+            if pin.port { '1' } else { '0' },
+            pin.pin
+        );
+
+        Self {
+            timer,
+            pin,
+            channel,
+        }
+    }
+
+    /// Waits for the next high pulse (a rising edge followed by a falling edge) and returns its
+    /// width
+    ///
+    /// Returns `None` if the pulse is not over before `timeout`
+    pub fn measure_pulse(&mut self, gpiote: &GPIOTE, timeout: Duration) -> Option<Duration> {
+        self.restart(gpiote);
+
+        let mut rise = self.wait_rising_edge(gpiote, timeout)?;
+        loop {
+            let (time, rising) = self.wait_edge(gpiote, timeout)?;
+            if rising {
+                // the falling edge was missed; measure from the latest rising edge
+                rise = time;
+            } else {
+                return Some(Duration::from_micros(u64::from(time - rise)));
+            }
+        }
+    }
+
+    /// Waits for the next two rising edges and returns the time between them: the period of the
+    /// signal
+    ///
+    /// Returns `None` if the second edge doesn't arrive before `timeout`
+    pub fn measure_period(&mut self, gpiote: &GPIOTE, timeout: Duration) -> Option<Duration> {
+        self.restart(gpiote);
+
+        let first = self.wait_rising_edge(gpiote, timeout)?;
+        let second = self.wait_rising_edge(gpiote, timeout)?;
+        Some(Duration::from_micros(u64::from(second - first)))
+    }
+
+    /// Stops monitoring the signal and releases the TIMER1 peripheral and the pin
+    pub fn free(self, gpiote: &GPIOTE, ppi: &mut Ppi) -> (TIMER1, Pin<Input<Floating>>) {
+        ppi.disconnect(self.channel);
+        gpiote.config[CHANNEL].reset();
+        self.timer.tasks_stop.write(|w| unsafe { w.bits(1) });
+        (self.timer, self.pin)
+    }
+
+    // clears the timestamps and restarts the timer from zero
+    fn restart(&mut self, gpiote: &GPIOTE) {
+        self.timer.tasks_stop.write(|w| unsafe { w.bits(1) });
+        self.timer.tasks_clear.write(|w| unsafe { w.bits(1) });
+        self.timer.cc[0].reset();
+        gpiote.events_in[CHANNEL].reset();
+        self.timer.tasks_start.write(|w| unsafe { w.bits(1) });
+    }
+
+    // returns the time of the next rising edge
+    fn wait_rising_edge(&mut self, gpiote: &GPIOTE, timeout: Duration) -> Option<u32> {
+        loop {
+            let (time, rising) = self.wait_edge(gpiote, timeout)?;
+            if rising {
+                return Some(time);
+            }
+        }
+    }
+
+    // returns the time of the next edge and whether it was a rising edge
+    fn wait_edge(&mut self, gpiote: &GPIOTE, timeout: Duration) -> Option<(u32, bool)> {
+        while gpiote.events_in[CHANNEL].read().bits() == 0 {
+            if self.timed_out(timeout) {
+                return None;
+            }
+        }
+        gpiote.events_in[CHANNEL].reset();
+
+        let time = self.timer.cc[0].read().bits();
+        // the pin is high after a rising edge
+        let rising = self.pin.is_high() == Ok(true);
+        Some((time, rising))
+    }
+
+    fn timed_out(&mut self, timeout: Duration) -> bool {
+        self.timer.tasks_capture[1].write(|w| unsafe { w.bits(1) });
+        u128::from(self.timer.cc[1].read().bits()) >= timeout.as_micros()
+    }
+}
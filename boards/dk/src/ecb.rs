@@ -0,0 +1,77 @@
+//! AES-128 encryption using the AES ECB peripheral
+//!
+//! The ECB peripheral encrypts one 16-byte block at a time and can't decrypt. It is not the
+//! CryptoCell (CC310), which can only be used through Nordic's binary library
+
+use core::sync::atomic::{self, Ordering};
+
+use hal::target::ECB;
+
+/// Size of an AES-128 key and block, in bytes
+pub const AES_BLOCK_SIZE: usize = 16;
+
+// layout expected by the ECB peripheral; see section 6.5.1 of the nRF52840 Product Specification
+#[repr(C)]
+struct EcbData {
+    key: [u8; AES_BLOCK_SIZE],
+    cleartext: [u8; AES_BLOCK_SIZE],
+    ciphertext: [u8; AES_BLOCK_SIZE],
+}
+
+/// AES-128 block cipher (encryption only) backed by the AES ECB peripheral
+///
+/// The ECB peripheral can be obtained from the `unused` field of the `Board`
+pub struct Aes128 {
+    ecb: ECB,
+    data: EcbData,
+}
+
+impl Aes128 {
+    /// Creates a new cipher that uses the given `key`
+    pub fn new(ecb: ECB, key: &[u8; AES_BLOCK_SIZE]) -> Self {
+        Self {
+            ecb,
+            data: EcbData {
+                key: *key,
+                cleartext: [0; AES_BLOCK_SIZE],
+                ciphertext: [0; AES_BLOCK_SIZE],
+            },
+        }
+    }
+
+    /// Encrypts a single block in place
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the operation is aborted by the hardware (e.g. by the CCM or AAR
+    /// peripherals, which have higher priority)
+    pub fn encrypt_block(&mut self, block: &mut [u8; AES_BLOCK_SIZE]) {
+        self.data.cleartext = *block;
+
+        let ptr = &mut self.data as *mut EcbData as u32;
+        self.ecb.events_endecb.reset();
+        self.ecb.events_errorecb.reset();
+        self.ecb
+            .ecbdataptr
+            .write(|w| unsafe { w.ecbdataptr().bits(ptr) });
+
+        // start DMA transfer
+        atomic::fence(Ordering::Release);
+        self.ecb.tasks_startecb.write(|w| unsafe { w.bits(1) });
+        while self.ecb.events_endecb.read().bits() == 0 {
+            assert!(
+                self.ecb.events_errorecb.read().bits() == 0,
+                "ECB: operation aborted"
+            );
+        }
+        self.ecb.events_endecb.reset();
+        atomic::fence(Ordering::Acquire);
+
+        *block = self.data.ciphertext;
+    }
+
+    /// Releases the ECB peripheral
+    pub fn free(self) -> ECB {
+        self.ecb
+    }
+}
@@ -0,0 +1,383 @@
+//! Enhanced ShockBurst (ESB)
+//!
+//! ESB is the packet protocol of Nordic's nRF24 radios and of the `nrf_esb` library. Compared to
+//! the fire-and-forget `Radio::send`, ESB adds *reliable delivery*: the receiver (PRX)
+//! acknowledges each packet and the transmitter (PTX) retransmits a packet until it's
+//! acknowledged or it runs out of attempts. Retransmitted packets carry the same packet ID (PID)
+//! so the receiver can discard duplicates.
+//!
+//! This implementation uses dynamic payload lengths, 5-byte addresses, a 16-bit CRC and the
+//! 2 Mbit/s data rate, the default configuration of `nrf_esb`. Acknowledgments carry no payload.
+
+use core::{
+    sync::atomic::{self, Ordering},
+    time::Duration,
+};
+
+use hal::target::RADIO;
+
+use crate::radio::Radio;
+
+/// Maximum payload size, in bytes
+pub const MAX_PAYLOAD_LEN: usize = 32;
+
+/// Number of pipes
+pub const PIPES: u8 = 8;
+
+// LENGTH (1 byte) + S1 (1 byte) + payload
+const BUFFER_LEN: usize = 2 + MAX_PAYLOAD_LEN;
+
+// CRC configuration: x**16 + x**12 + x**5 + 1 with an initial value of `0xFFFF`
+const CRC_POLY: u32 = 0x1_1021;
+const CRC_INIT: u32 = 0xFFFF;
+
+/// Addresses of the 8 pipes
+///
+/// The address of pipe 0 is `base0` followed by `prefixes[0]`; the address of pipe `n`, for `n`
+/// in `1..8`, is `base1` followed by `prefixes[n]`. The addresses are written least significant
+/// byte first, like in `nrf_esb`
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Addresses {
+    /// Base address of pipe 0
+    pub base0: [u8; 4],
+    /// Base address of pipes 1 to 7
+    pub base1: [u8; 4],
+    /// Prefix (last byte) of the address of each pipe
+    pub prefixes: [u8; 8],
+}
+
+impl Default for Addresses {
+    /// The default addresses of `nrf_esb`
+    fn default() -> Self {
+        Self {
+            base0: [0xE7, 0xE7, 0xE7, 0xE7],
+            base1: [0xC2, 0xC2, 0xC2, 0xC2],
+            prefixes: [0xE7, 0xC2, 0xC3, 0xC4, 0xC5, 0xC6, 0xC7, 0xC8],
+        }
+    }
+}
+
+/// Error returned by `Esb::send`
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error {
+    /// The packet was not acknowledged after all the retransmission attempts
+    MaxRetransmits,
+}
+
+/// Packet received with `Esb::recv`
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Received {
+    /// Pipe the packet was received on
+    pub pipe: u8,
+    /// Size of the payload, in bytes
+    pub len: usize,
+}
+
+/// ESB transceiver
+///
+/// A transceiver acts as PTX when it calls `send` and as PRX when it calls `recv`
+pub struct Esb {
+    buffer: [u8; BUFFER_LEN],
+    // acknowledgments are sent from, and received into, this buffer; received acknowledgments may
+    // carry a payload so it must be as large as `buffer`
+    ack: [u8; BUFFER_LEN],
+    // PID of the last packet sent on each pipe
+    tx_pids: [u8; PIPES as usize],
+    // PID and CRC of the last packet received on each pipe
+    rx_last: [Option<(u8, u32)>; PIPES as usize],
+    retransmits: u8,
+    retransmit_delay: Duration,
+    radio: RADIO,
+}
+
+impl Esb {
+    /// Turns the IEEE 802.15.4 `radio` into an ESB transceiver
+    ///
+    /// The transceiver starts with the `Addresses::default()` addresses, on channel 2 and with
+    /// 3 retransmissions spaced 250 us apart. It uses the TX power that was last configured with
+    /// `set_txpower`
+    pub fn new(radio: Radio) -> Self {
+        let radio = radio.into_registers();
+
+        radio.mode.write(|w| w.mode().nrf_2mbit());
+        // ramp-up time must be the one of the nRF24 radios (~130 us)
+        radio.modecnf0.write(|w| w.ru().default());
+        // NOTE(unsafe) the radio is disabled
+        unsafe {
+            radio.pcnf0.write(|w| {
+                w.lflen()
+                    .bits(6) // 6-bit payload length
+                    .s0len()
+                    .clear_bit() // no S0
+                    .s1len()
+                    .bits(3) // 2-bit PID + 1-bit NO_ACK
+                    .s1incl()
+                    .clear_bit()
+                    .cilen()
+                    .bits(0)
+                    .plen()
+                    ._8bit() // 1-byte preamble
+                    .crcinc()
+                    .exclude() // LENGTH does not account for the CRC
+            });
+            radio.pcnf1.write(|w| {
+                w.maxlen()
+                    .bits(MAX_PAYLOAD_LEN as u8)
+                    .statlen()
+                    .bits(0)
+                    .balen()
+                    .bits(4) // 4-byte base address + 1-byte prefix
+                    .endian()
+                    .big()
+                    .whiteen()
+                    .disabled()
+            });
+
+            radio.crccnf.write(|w| w.len().two().skipaddr().include());
+            radio.crcpoly.write(|w| w.crcpoly().bits(CRC_POLY));
+            radio.crcinit.write(|w| w.crcinit().bits(CRC_INIT));
+        }
+
+        let mut esb = Self {
+            buffer: [0; BUFFER_LEN],
+            ack: [0; BUFFER_LEN],
+            tx_pids: [0; PIPES as usize],
+            rx_last: [None; PIPES as usize],
+            retransmits: 3,
+            retransmit_delay: Duration::from_micros(250),
+            radio,
+        };
+        esb.set_addresses(&Addresses::default());
+        esb.set_channel(2);
+        esb
+    }
+
+    /// Changes the pipe addresses
+    pub fn set_addresses(&mut self, addresses: &Addresses) {
+        // the nRF24 radios send the bits of each byte in the opposite order
+        let prefix = |prefixes: &[u8]| {
+            u32::from_le_bytes([
+                prefixes[0].reverse_bits(),
+                prefixes[1].reverse_bits(),
+                prefixes[2].reverse_bits(),
+                prefixes[3].reverse_bits(),
+            ])
+        };
+
+        // NOTE(unsafe) the radio is disabled outside `send` and `recv`
+        unsafe {
+            self.radio.base0.write(|w| {
+                w.base0()
+                    .bits(u32::from_le_bytes(addresses.base0).reverse_bits())
+            });
+            self.radio.base1.write(|w| {
+                w.base1()
+                    .bits(u32::from_le_bytes(addresses.base1).reverse_bits())
+            });
+            self.radio
+                .prefix0
+                .write(|w| w.bits(prefix(&addresses.prefixes[..4])));
+            self.radio
+                .prefix1
+                .write(|w| w.bits(prefix(&addresses.prefixes[4..])));
+        }
+
+        self.rx_last = [None; PIPES as usize];
+    }
+
+    /// Changes the radio channel; the radio will operate at a frequency of `2400 + channel` MHz
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `channel` is greater than 100
+    pub fn set_channel(&mut self, channel: u8) {
+        assert!(channel <= 100, "invalid channel");
+
+        // NOTE(unsafe) any value in the range `0..=100` is valid
+        self.radio
+            .frequency
+            .write(|w| unsafe { w.frequency().bits(channel).map().default() });
+    }
+
+    /// Changes the number of retransmissions and the time the PTX waits for an acknowledgment
+    /// before retransmitting a packet
+    pub fn set_retransmits(&mut self, count: u8, delay: Duration) {
+        self.retransmits = count;
+        self.retransmit_delay = delay;
+    }
+
+    /// Sends `payload` on the given `pipe`
+    ///
+    /// If `ack` is `true` the packet is retransmitted until the PRX acknowledges it; on success
+    /// the number of retransmissions is returned. If `ack` is `false` the packet is sent once and
+    /// this function always returns `Ok(0)`
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `pipe` is not a valid pipe or if `payload` is longer than
+    /// `MAX_PAYLOAD_LEN` bytes
+    pub fn send(&mut self, pipe: u8, payload: &[u8], ack: bool) -> Result<u8, Error> {
+        assert!(pipe < PIPES, "invalid pipe");
+        assert!(payload.len() <= MAX_PAYLOAD_LEN, "payload is too long");
+
+        let pid = (self.tx_pids[usize::from(pipe)] + 1) % 4;
+        self.tx_pids[usize::from(pipe)] = pid;
+
+        self.buffer[0] = payload.len() as u8;
+        // `nrf_esb` sets this bit when the packet must be acknowledged
+        self.buffer[1] = pid << 1 | ack as u8;
+        self.buffer[2..2 + payload.len()].copy_from_slice(payload);
+
+        // NOTE(unsafe) any pipe in the range `0..8` is valid
+        unsafe {
+            self.radio.txaddress.write(|w| w.txaddress().bits(pipe));
+            self.radio.rxaddresses.write(|w| w.bits(1 << pipe));
+        }
+
+        for attempt in 0..=self.retransmits {
+            if attempt != 0 {
+                log::trace!("esb: retransmitting (attempt {})", attempt);
+            }
+
+            self.radio.shorts.write(|w| {
+                w.ready_start().enabled().end_disable().enabled();
+                // switch to RX right after the transmission to catch the acknowledgment
+                if ack {
+                    w.disabled_rxen().enabled();
+                }
+                w
+            });
+            self.set_packetptr(self.buffer.as_ptr());
+            atomic::compiler_fence(Ordering::Release);
+            self.radio.tasks_txen.write(|w| w.tasks_txen().set_bit());
+            self.wait_disabled();
+            atomic::compiler_fence(Ordering::Acquire);
+
+            if !ack {
+                return Ok(0);
+            }
+
+            // the radio is ramping up for RX; this takes effect before the packet is received
+            self.radio
+                .shorts
+                .write(|w| w.ready_start().enabled().end_disable().enabled());
+            self.set_packetptr(self.ack.as_ptr());
+            atomic::compiler_fence(Ordering::Release);
+
+            let deadline = crate::uptime() + self.retransmit_delay;
+            let acked = loop {
+                if self.radio.events_disabled.read().bits() != 0 {
+                    break self.radio.crcstatus.read().crcstatus().is_crcok();
+                }
+
+                if crate::uptime() >= deadline {
+                    self.radio
+                        .tasks_disable
+                        .write(|w| w.tasks_disable().set_bit());
+                    while self.radio.events_disabled.read().bits() == 0 {}
+                    break false;
+                }
+            };
+            self.radio.events_disabled.reset();
+            atomic::compiler_fence(Ordering::Acquire);
+
+            if acked {
+                log::trace!("esb: packet acknowledged");
+                return Ok(attempt);
+            }
+        }
+
+        log::debug!("esb: packet was not acknowledged");
+        Err(Error::MaxRetransmits)
+    }
+
+    /// Waits until a packet is received on any of the pipes and copies its payload into `buffer`
+    ///
+    /// Packets are acknowledged as requested by the PTX. Packets that fail the CRC check and
+    /// retransmissions of the last received packet are discarded. If the payload doesn't fit in
+    /// `buffer` it's truncated
+    pub fn recv(&mut self, buffer: &mut [u8]) -> Received {
+        // NOTE(unsafe) all bits are valid addresses
+        self.radio.rxaddresses.write(|w| unsafe { w.bits(0xff) });
+
+        loop {
+            // send the acknowledgment right after the reception; the transmission is cancelled
+            // below if the packet must not be acknowledged
+            self.radio.shorts.write(|w| {
+                w.ready_start()
+                    .enabled()
+                    .end_disable()
+                    .enabled()
+                    .disabled_txen()
+                    .enabled()
+            });
+            self.set_packetptr(self.buffer.as_ptr());
+            atomic::compiler_fence(Ordering::Release);
+            self.radio.tasks_rxen.write(|w| w.tasks_rxen().set_bit());
+            self.wait_disabled();
+            atomic::compiler_fence(Ordering::Acquire);
+
+            // the radio is now ramping up for TX
+            self.radio
+                .shorts
+                .write(|w| w.ready_start().enabled().end_disable().enabled());
+
+            let crc_ok = self.radio.crcstatus.read().crcstatus().is_crcok();
+            let pipe = self.radio.rxmatch.read().rxmatch().bits();
+            let crc = self.radio.rxcrc.read().rxcrc().bits();
+            let len = usize::from(self.buffer[0]);
+            let pid = self.buffer[1] >> 1;
+            let ack = self.buffer[1] & 1 != 0;
+
+            if crc_ok && ack {
+                self.ack[0] = 0;
+                self.ack[1] = pid << 1;
+                // NOTE(unsafe) `pipe` is in the range `0..8`
+                self.radio
+                    .txaddress
+                    .write(|w| unsafe { w.txaddress().bits(pipe) });
+                self.set_packetptr(self.ack.as_ptr());
+                atomic::compiler_fence(Ordering::Release);
+                self.wait_disabled();
+            } else {
+                self.radio
+                    .tasks_disable
+                    .write(|w| w.tasks_disable().set_bit());
+                self.wait_disabled();
+            }
+
+            if !crc_ok {
+                log::trace!("esb: dropped packet with invalid CRC");
+                continue;
+            }
+
+            let last = &mut self.rx_last[usize::from(pipe)];
+            if *last == Some((pid, crc)) {
+                log::trace!("esb: dropped retransmitted packet");
+                continue;
+            }
+            *last = Some((pid, crc));
+
+            let len = len.min(MAX_PAYLOAD_LEN).min(buffer.len());
+            buffer[..len].copy_from_slice(&self.buffer[2..2 + len]);
+            let received = Received { pipe, len };
+            log::trace!("esb: {:?}", received);
+            return received;
+        }
+    }
+
+    fn set_packetptr(&self, ptr: *const u8) {
+        // NOTE(unsafe) the pointer points into a buffer owned by `self`, which is in RAM
+        self.radio
+            .packetptr
+            .write(|w| unsafe { w.packetptr().bits(ptr as u32) });
+    }
+
+    fn wait_disabled(&self) {
+        while self.radio.events_disabled.read().bits() == 0 {}
+        self.radio.events_disabled.reset();
+    }
+}
@@ -0,0 +1,79 @@
+//! GPIO pins
+//!
+//! This module re-exports the GPIO API of the HAL and adds drive strength and pull resistor
+//! configuration for output pins (see `OutputConfig`). To enable the pull resistor of an input
+//! pin, e.g. for a button without an external resistor, use the `into_pullup_input` and
+//! `into_pulldown_input` methods of the pin
+
+pub use hal::gpio::*;
+use hal::target::{p0::PIN_CNF, P0, P1};
+
+/// Drive strength of an output pin
+///
+/// The "standard" drive provides up to 2 mA; the "high" drive up to 10 mA (see section 6.9, GPIO,
+/// of the nRF52840 Product Specification)
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Drive {
+    /// Push-pull output with standard drive; the default
+    Standard,
+    /// Push-pull output with high drive
+    High,
+    /// Open-drain output with standard drive: the pin is only driven when it's low
+    OpenDrain,
+    /// Open-drain output with high drive: the pin is only driven when it's low
+    OpenDrainHigh,
+}
+
+/// Pull resistor configuration
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Pull {
+    /// No pull resistor; the default
+    Disabled,
+    /// Pull-up resistor (~13 kOhm)
+    Up,
+    /// Pull-down resistor (~13 kOhm)
+    Down,
+}
+
+/// Electrical configuration of output pins
+///
+/// For example, an I2C bus without external pull-up resistors needs open-drain outputs with the
+/// internal pull-up resistor enabled
+pub trait OutputConfig {
+    /// Changes the drive strength of the pin
+    fn set_drive(&mut self, drive: Drive);
+
+    /// Enables or disables the internal pull resistor of the pin
+    ///
+    /// A pull resistor is only useful if the pin is not always driven: see `Drive::OpenDrain`
+    fn set_pull(&mut self, pull: Pull);
+}
+
+impl<MODE> OutputConfig for Pin<Output<MODE>> {
+    fn set_drive(&mut self, drive: Drive) {
+        pin_cnf(self).modify(|_, w| match drive {
+            Drive::Standard => w.drive().s0s1(),
+            Drive::High => w.drive().h0h1(),
+            Drive::OpenDrain => w.drive().s0d1(),
+            Drive::OpenDrainHigh => w.drive().h0d1(),
+        });
+    }
+
+    fn set_pull(&mut self, pull: Pull) {
+        pin_cnf(self).modify(|_, w| match pull {
+            Pull::Disabled => w.pull().disabled(),
+            Pull::Up => w.pull().pullup(),
+            Pull::Down => w.pull().pulldown(),
+        });
+    }
+}
+
+fn pin_cnf<MODE>(pin: &Pin<MODE>) -> &'static PIN_CNF {
+    // NOTE(unsafe) `pin` owns the pin so this is the only reference to its PIN_CNF register
+    unsafe {
+        let port = if pin.port { P1::ptr() } else { P0::ptr() };
+        &(*port).pin_cnf[usize::from(pin.pin)]
+    }
+}
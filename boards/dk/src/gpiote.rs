@@ -0,0 +1,70 @@
+//! GPIOTE (GPIO Tasks and Events) peripheral
+
+use crate::peripheral::GPIOTE;
+
+// GPIOTE channel `N` monitors the pin at index `N` of this list
+// (BUTTON1 = P0.11, BUTTON2 = P0.12, BUTTON3 = P0.24, BUTTON4 = P0.25)
+const BUTTON_PINS: [u8; 4] = [11, 12, 24, 25];
+
+/// Configures GPIOTE channels 0 to 3 to generate an event when one of the buttons is pressed
+///
+/// This also unmasks the corresponding interrupts; the GPIOTE interrupt still needs to be enabled
+/// in the NVIC, for example by binding an RTIC task to it
+pub fn init(gpiote: &GPIOTE) {
+    for (channel, pin) in BUTTON_PINS.iter().enumerate() {
+        // the buttons pull the pin low when pressed
+        gpiote.config[channel].write(|w| {
+            w.mode().event();
+            unsafe { w.psel().bits(*pin) };
+            w.port().clear_bit();
+            w.polarity().hi_to_lo()
+        });
+        gpiote.events_in[channel].reset();
+    }
+
+    gpiote.intenset.write(|w| {
+        w.in0().set_bit();
+        w.in1().set_bit();
+        w.in2().set_bit();
+        w.in3().set_bit()
+    });
+}
+
+/// GPIOTE.EVENTS_IN registers mapped to an enum
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Event {
+    /// `EVENTS_IN[0]` register was active: BUTTON1 was pressed
+    Button1Pressed,
+
+    /// `EVENTS_IN[1]` register was active: BUTTON2 was pressed
+    Button2Pressed,
+
+    /// `EVENTS_IN[2]` register was active: BUTTON3 was pressed
+    Button3Pressed,
+
+    /// `EVENTS_IN[3]` register was active: BUTTON4 was pressed
+    Button4Pressed,
+}
+
+/// Returns the next unhandled button event; returns none if there's no event to handle
+///
+/// NOTE this function will clear the corresponding EVENT register
+pub fn next_event(gpiote: &GPIOTE) -> Option<Event> {
+    const EVENTS: [Event; 4] = [
+        Event::Button1Pressed,
+        Event::Button2Pressed,
+        Event::Button3Pressed,
+        Event::Button4Pressed,
+    ];
+
+    for (channel, event) in EVENTS.iter().enumerate() {
+        if gpiote.events_in[channel].read().bits() != 0 {
+            gpiote.events_in[channel].reset();
+
+            return Some(*event);
+        }
+    }
+
+    None
+}
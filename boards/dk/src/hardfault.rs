@@ -0,0 +1,40 @@
+//! HardFault handler that reports the fault over RTT
+//!
+//! The report goes through the `log` logger so it's only printed if `dk::init` has been called
+
+use cortex_m::{asm, peripheral::SCB};
+use cortex_m_rt::{exception, ExceptionFrame};
+
+#[exception]
+fn HardFault(ef: &ExceptionFrame) -> ! {
+    // NOTE(unsafe) read-only accesses to status registers
+    let scb = unsafe { &*SCB::ptr() };
+
+    log::error!("HardFault");
+    log::error!(
+        "r0=0x{:08x} r1=0x{:08x} r2=0x{:08x} r3=0x{:08x}",
+        ef.r0,
+        ef.r1,
+        ef.r2,
+        ef.r3
+    );
+    log::error!(
+        "r12=0x{:08x} lr=0x{:08x} pc=0x{:08x} xpsr=0x{:08x}",
+        ef.r12,
+        ef.lr,
+        ef.pc,
+        ef.xpsr
+    );
+    // see section 4.3 of the Cortex-M4 Devices Generic User Guide for the meaning of these bits
+    log::error!(
+        "CFSR=0x{:08x} HFSR=0x{:08x} MMFAR=0x{:08x} BFAR=0x{:08x}",
+        scb.cfsr.read(),
+        scb.hfsr.read(),
+        scb.mmfar.read(),
+        scb.bfar.read()
+    );
+
+    loop {
+        asm::bkpt()
+    }
+}
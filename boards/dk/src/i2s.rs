@@ -0,0 +1,189 @@
+//! I2S (Inter-IC Sound) audio output
+
+use core::sync::atomic::{self, Ordering};
+
+use hal::{
+    gpio::{Output, Pin, PushPull},
+    target::I2S,
+};
+
+/// Number of frames in each of the buffers returned by `I2s::next_buffer`
+pub const BUFFER_LEN: usize = 256;
+
+/// Sample rate, in Hz (4 MHz master clock with a ratio of 256)
+pub const SAMPLE_RATE: u32 = 15_625;
+
+/// A stereo frame: the left and right samples
+pub type Frame = [i16; 2];
+
+/// I2S master that sends 16-bit stereo audio to a DAC or amplifier
+///
+/// Samples are read, using EasyDMA, from a ring of two buffers of `BUFFER_LEN` frames: while the
+/// hardware plays one of them the application fills the other one.
+pub struct I2s {
+    inner: I2S,
+    buffers: &'static mut [[Frame; BUFFER_LEN]; 2],
+    // index of the buffer that is being played by the hardware
+    current: usize,
+    running: bool,
+}
+
+impl I2s {
+    /// Configures the I2S peripheral as a master on the given pins
+    ///
+    /// `mck`, the master clock, can be omitted if the DAC doesn't need it. The I2S peripheral can
+    /// be obtained from the `unused` field of the `Board`; the pins from its `pins` field
+    pub fn new(
+        i2s: I2S,
+        sck: Pin<Output<PushPull>>,
+        lrck: Pin<Output<PushPull>>,
+        sdout: Pin<Output<PushPull>>,
+        mck: Option<Pin<Output<PushPull>>>,
+    ) -> Self {
+        // NOTE(static mut) `I2S` is a singleton and `I2s` owns it so at most one reference to
+        // these buffers exists at any time
+        static mut BUFFERS: [[Frame; BUFFER_LEN]; 2] = [[[0; 2]; BUFFER_LEN]; 2];
+        let buffers = unsafe { &mut BUFFERS };
+
+        i2s.psel.sck.write(|w| {
+            unsafe { w.pin().bits(sck.pin) };
+            w.port().bit(sck.port);
+            w.connect().connected()
+        });
+        i2s.psel.lrck.write(|w| {
+            unsafe { w.pin().bits(lrck.pin) };
+            w.port().bit(lrck.port);
+            w.connect().connected()
+        });
+        i2s.psel.sdout.write(|w| {
+            unsafe { w.pin().bits(sdout.pin) };
+            w.port().bit(sdout.port);
+            w.connect().connected()
+        });
+        if let Some(mck) = &mck {
+            i2s.psel.mck.write(|w| {
+                unsafe { w.pin().bits(mck.pin) };
+                w.port().bit(mck.port);
+                w.connect().connected()
+            });
+        }
+
+        i2s.config.mode.write(|w| w.mode().master());
+        i2s.config.txen.write(|w| w.txen().enabled());
+        i2s.config.rxen.write(|w| w.rxen().disabled());
+        i2s.config.mcken.write(|w| w.mcken().bit(mck.is_some()));
+        // 32 MHz / 8 = 4 MHz; 4 MHz / 256 = 15.625 kHz
+        i2s.config.mckfreq.write(|w| w.mckfreq()._32mdiv8());
+        i2s.config.ratio.write(|w| w.ratio()._256x());
+        i2s.config.swidth.write(|w| w.swidth()._16bit());
+        i2s.config.align.write(|w| w.align().left());
+        i2s.config.format.write(|w| w.format().i2s());
+        i2s.config.channels.write(|w| w.channels().stereo());
+        // one 32-bit word per frame
+        i2s.rxtxd
+            .maxcnt
+            .write(|w| unsafe { w.maxcnt().bits(BUFFER_LEN as u16) });
+        i2s.enable.write(|w| w.enable().enabled());
+
+        log::debug!(
+            "I2S: SCK = P{}.{}, LRCK = P{}.{}, SDOUT = P{}.{}",
+            if sck.port { '1' } else { '0' },
+            sck.pin,
+            if lrck.port { '1' } else { '0' },
+            lrck.pin,
+            if sdout.port { '1' } else { '0' },
+            sdout.pin
+        );
+
+        Self {
+            inner: i2s,
+            buffers,
+            current: 0,
+            running: false,
+        }
+    }
+
+    /// Starts playing; both buffers start filled with silence
+    ///
+    /// # Panics
+    ///
+    /// This function panics if playback has already been started
+    pub fn start(&mut self) {
+        assert!(!self.running, "I2S: playback has already been started");
+
+        for buffer in self.buffers.iter_mut() {
+            *buffer = [[0; 2]; BUFFER_LEN];
+        }
+        self.running = true;
+        self.current = 0;
+        self.set_buffer(0);
+
+        // start DMA transfer
+        atomic::fence(Ordering::Release);
+        self.inner.tasks_start.write(|w| unsafe { w.bits(1) });
+
+        // the first buffer has been captured by the hardware; queue the second one
+        self.wait_txptrupd();
+        self.set_buffer(1);
+    }
+
+    /// Waits until the hardware is done with a buffer and returns it so it can be filled with the
+    /// next frames to play
+    ///
+    /// The returned buffer has already been queued so it will be played as soon as the other
+    /// buffer is over: the application must fill it, and call this method again, within
+    /// `BUFFER_LEN / SAMPLE_RATE` seconds (~16 ms) or the hardware will replay old frames
+    ///
+    /// # Panics
+    ///
+    /// This function panics if playback has not been started with `start`
+    pub fn next_buffer(&mut self) -> &mut [Frame; BUFFER_LEN] {
+        assert!(self.running, "I2S: playback has not been started");
+
+        // make the frames written into the previously returned buffer visible to the DMA
+        atomic::fence(Ordering::Release);
+
+        // the hardware has moved on to the other buffer; queue the one that was just played
+        self.wait_txptrupd();
+        let done = self.current;
+        self.current = 1 - done;
+        self.set_buffer(done);
+
+        &mut self.buffers[done]
+    }
+
+    /// Stops playing
+    pub fn stop(&mut self) {
+        if !self.running {
+            return;
+        }
+        self.running = false;
+
+        self.inner.tasks_stop.write(|w| unsafe { w.bits(1) });
+        while self.inner.events_stopped.read().bits() == 0 {
+            continue;
+        }
+        self.inner.events_stopped.reset();
+        self.inner.events_txptrupd.reset();
+        atomic::fence(Ordering::Acquire);
+    }
+
+    /// Releases the I2S peripheral
+    pub fn free(mut self) -> I2S {
+        self.stop();
+        self.inner.enable.write(|w| w.enable().disabled());
+        self.inner
+    }
+
+    fn set_buffer(&mut self, index: usize) {
+        let ptr = self.buffers[index].as_ptr() as u32;
+        self.inner.txd.ptr.write(|w| unsafe { w.ptr().bits(ptr) });
+    }
+
+    fn wait_txptrupd(&mut self) {
+        while self.inner.events_txptrupd.read().bits() == 0 {
+            continue;
+        }
+        self.inner.events_txptrupd.reset();
+    }
+}
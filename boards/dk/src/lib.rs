@@ -4,48 +4,113 @@
 #![deny(warnings)]
 #![no_std]
 
+#[cfg(feature = "defmt")]
+use core::fmt;
 use core::{
-    ops,
+    cmp, ops,
     sync::atomic::{self, AtomicU32, Ordering},
     time::Duration,
 };
 
-use cortex_m::asm;
-use embedded_hal::digital::v2::{OutputPin as _, StatefulOutputPin};
+use cortex_m::{asm, peripheral::NVIC};
+#[cfg(feature = "defmt")]
+use defmt_rtt as _; // global logger
+#[cfg(feature = "periodic-timer")]
+use embedded_hal::timer::Cancel;
+use embedded_hal::{
+    blocking::delay::{DelayMs, DelayUs},
+    digital::v2::{InputPin, OutputPin, StatefulOutputPin},
+    timer::CountDown,
+};
 #[cfg(feature = "beginner")]
 pub use hal::ieee802154;
-pub use hal::target::{interrupt, Interrupt, NVIC_PRIO_BITS, RTC0};
+pub use hal::target::{interrupt, Interrupt, Peripherals, NVIC_PRIO_BITS, RTC0};
+#[cfg(feature = "periodic-timer")]
+use hal::timer::Periodic;
 use hal::{
     clocks::{self, Clocks},
-    gpio::{p0, Level, Output, Pin, PushPull},
-    rtc::{Rtc, RtcInterrupt},
+    gpio::{p0, p1, Floating, Input, Level, Output, Pin, PullUp, PushPull},
+    rtc::{self, RtcInterrupt},
+    target::RTC1,
     timer::OneShot,
 };
 use log::{LevelFilter, Log};
+#[cfg(not(feature = "defmt"))]
 use rtt_target::{rprintln, rtt_init_print};
+use void::Void;
 
+#[cfg(feature = "panic-persist")]
+pub use crate::panic_persist::get_last_panic;
+use crate::{
+    peripheral::{Unused, GPIOTE, PWM0},
+    wdt::Watchdog,
+};
 #[cfg(feature = "advanced")]
 use crate::{
     peripheral::{POWER, USBD},
     usbd::Ep0In,
 };
 
+pub mod adc;
+#[cfg(feature = "beginner")]
+pub mod ble;
+pub mod capture;
+pub mod ecb;
 #[cfg(feature = "advanced")]
 mod errata;
+#[cfg(feature = "beginner")]
+pub mod esb;
+pub mod gpio;
+pub mod gpiote;
+#[cfg(feature = "hardfault")]
+mod hardfault;
+pub mod i2s;
+#[cfg(feature = "beginner")]
+pub mod mac;
+#[cfg(feature = "panic-persist")]
+mod panic_persist;
+pub mod pattern;
+pub mod pdm;
 pub mod peripheral;
+pub mod ppi;
+pub mod qspi;
+#[cfg(feature = "beginner")]
+pub mod radio;
+pub mod sha256;
+pub mod spim;
+pub mod twim;
+pub mod uarte;
 #[cfg(feature = "advanced")]
 pub mod usbd;
+pub mod wdt;
 
 /// Components on the board
 pub struct Board {
     /// LEDs
     pub leds: Leds,
+    /// Buttons
+    pub buttons: Buttons,
+    /// GPIOTE (GPIO Tasks and Events) peripheral; see the `gpiote` module
+    pub gpiote: GPIOTE,
+    /// PWM0 (Pulse Width Modulation) peripheral; see `Leds::into_dimmable`
+    pub pwm: PWM0,
     /// Timer
     pub timer: Timer,
+    /// Low power timer
+    pub rtc: Rtc,
+    /// Watchdog timer
+    pub watchdog: Watchdog,
+    /// Peripherals not used by the board, for use in the application
+    pub unused: Unused,
+    /// Header pins not used by the board
+    pub pins: Pins,
 
     /// Radio interface
+    ///
+    /// The TX power is initially set to its maximum value (+8 dBm); use `Radio::set_txpower` to
+    /// pick a value in the range of -40 dBm to +8 dBm
     #[cfg(feature = "beginner")]
-    pub radio: ieee802154::Radio<'static>,
+    pub radio: radio::Radio,
     /// USBD (Universal Serial Bus Device) peripheral
     #[cfg(feature = "advanced")]
     pub usbd: USBD,
@@ -69,6 +134,92 @@ pub struct Leds {
     pub _4: Led,
 }
 
+/// Pins routed to the board headers that are not used by the `Board`
+///
+/// The pins start as floating inputs; use the `into_*` methods to reconfigure them (e.g.
+/// `into_push_pull_output`). Pins P0.00 and P0.01 (32.768 kHz crystal) and P0.18 (reset button) are
+/// not included
+pub struct Pins {
+    /// P0.02
+    pub p0_02: p0::P0_02<Input<Floating>>,
+    /// P0.03
+    pub p0_03: p0::P0_03<Input<Floating>>,
+    /// P0.04
+    pub p0_04: p0::P0_04<Input<Floating>>,
+    /// P0.05; connected to the UART RTS line of the J-Link virtual COM port
+    pub p0_05: p0::P0_05<Input<Floating>>,
+    /// P0.06; connected to the UART TXD line of the J-Link virtual COM port (see the `uarte`
+    /// module)
+    pub p0_06: p0::P0_06<Input<Floating>>,
+    /// P0.07; connected to the UART CTS line of the J-Link virtual COM port
+    pub p0_07: p0::P0_07<Input<Floating>>,
+    /// P0.08; connected to the UART RXD line of the J-Link virtual COM port (see the `uarte`
+    /// module)
+    pub p0_08: p0::P0_08<Input<Floating>>,
+    /// P0.09; connected to the NFC antenna. Only usable as GPIO if the UICR configures the NFC
+    /// pins as GPIO
+    pub p0_09: p0::P0_09<Input<Floating>>,
+    /// P0.10; connected to the NFC antenna. Only usable as GPIO if the UICR configures the NFC
+    /// pins as GPIO
+    pub p0_10: p0::P0_10<Input<Floating>>,
+    /// P0.17; connected to the QSPI flash memory (CS)
+    pub p0_17: p0::P0_17<Input<Floating>>,
+    /// P0.19; connected to the QSPI flash memory (SCK)
+    pub p0_19: p0::P0_19<Input<Floating>>,
+    /// P0.20; connected to the QSPI flash memory (IO0)
+    pub p0_20: p0::P0_20<Input<Floating>>,
+    /// P0.21; connected to the QSPI flash memory (IO1)
+    pub p0_21: p0::P0_21<Input<Floating>>,
+    /// P0.22; connected to the QSPI flash memory (IO2)
+    pub p0_22: p0::P0_22<Input<Floating>>,
+    /// P0.23; connected to the QSPI flash memory (IO3)
+    pub p0_23: p0::P0_23<Input<Floating>>,
+    /// P0.26; SDA pin of the Arduino header
+    pub p0_26: p0::P0_26<Input<Floating>>,
+    /// P0.27; SCL pin of the Arduino header
+    pub p0_27: p0::P0_27<Input<Floating>>,
+    /// P0.28
+    pub p0_28: p0::P0_28<Input<Floating>>,
+    /// P0.29
+    pub p0_29: p0::P0_29<Input<Floating>>,
+    /// P0.30
+    pub p0_30: p0::P0_30<Input<Floating>>,
+    /// P0.31
+    pub p0_31: p0::P0_31<Input<Floating>>,
+    /// P1.00
+    pub p1_00: p1::P1_00<Input<Floating>>,
+    /// P1.01
+    pub p1_01: p1::P1_01<Input<Floating>>,
+    /// P1.02
+    pub p1_02: p1::P1_02<Input<Floating>>,
+    /// P1.03
+    pub p1_03: p1::P1_03<Input<Floating>>,
+    /// P1.04
+    pub p1_04: p1::P1_04<Input<Floating>>,
+    /// P1.05
+    pub p1_05: p1::P1_05<Input<Floating>>,
+    /// P1.06
+    pub p1_06: p1::P1_06<Input<Floating>>,
+    /// P1.07
+    pub p1_07: p1::P1_07<Input<Floating>>,
+    /// P1.08
+    pub p1_08: p1::P1_08<Input<Floating>>,
+    /// P1.09
+    pub p1_09: p1::P1_09<Input<Floating>>,
+    /// P1.10
+    pub p1_10: p1::P1_10<Input<Floating>>,
+    /// P1.11
+    pub p1_11: p1::P1_11<Input<Floating>>,
+    /// P1.12
+    pub p1_12: p1::P1_12<Input<Floating>>,
+    /// P1.13; MOSI pin of the Arduino header
+    pub p1_13: p1::P1_13<Input<Floating>>,
+    /// P1.14; MISO pin of the Arduino header
+    pub p1_14: p1::P1_14<Input<Floating>>,
+    /// P1.15; SCK pin of the Arduino header
+    pub p1_15: p1::P1_15<Input<Floating>>,
+}
+
 /// A single LED
 pub struct Led {
     inner: Pin<Output<PushPull>>,
@@ -119,6 +270,341 @@ impl Led {
     }
 }
 
+/// NOTE these methods set the level of the pin; the LED is on when the pin is low
+impl OutputPin for Led {
+    type Error = Void;
+
+    fn set_low(&mut self) -> Result<(), Void> {
+        self.inner.set_low()
+    }
+
+    fn set_high(&mut self) -> Result<(), Void> {
+        self.inner.set_high()
+    }
+}
+
+impl StatefulOutputPin for Led {
+    fn is_set_high(&self) -> Result<bool, Void> {
+        self.inner.is_set_high()
+    }
+
+    fn is_set_low(&self) -> Result<bool, Void> {
+        self.inner.is_set_low()
+    }
+}
+
+impl Leds {
+    /// Plays an LED pattern, blocking until the pattern is over; all LEDs are off afterwards
+    ///
+    /// See the `pattern` module for ways to create patterns
+    pub fn play<P>(&mut self, pattern: P, timer: &mut Timer)
+    where
+        P: IntoIterator<Item = pattern::Step>,
+    {
+        for step in pattern {
+            let mut leds = [&mut self._1, &mut self._2, &mut self._3, &mut self._4];
+            for (i, led) in leds.iter_mut().enumerate() {
+                if step.leds & (1 << i) != 0 {
+                    led.on();
+                } else {
+                    led.off();
+                }
+            }
+
+            timer.wait(step.duration);
+        }
+
+        self._1.off();
+        self._2.off();
+        self._3.off();
+        self._4.off();
+    }
+
+    /// Hands the LEDs over to the PWM0 peripheral so their brightness can be adjusted
+    ///
+    /// All LEDs start in the OFF state
+    pub fn into_dimmable(self, pwm: PWM0) -> DimmableLeds {
+        // NOTE(static mut) `Leds` is a singleton and `DimmableLeds` owns it so at most one
+        // reference to this buffer exists at any time
+        static mut DUTY: [u16; 4] = [0; 4];
+        let duty = unsafe { &mut DUTY };
+
+        let pins = [
+            self._1.inner.pin,
+            self._2.inner.pin,
+            self._3.inner.pin,
+            self._4.inner.pin,
+        ];
+        for (channel, pin) in pins.iter().enumerate() {
+            pwm.psel.out[channel].write(|w| {
+                unsafe { w.pin().bits(*pin) };
+                w.port().clear_bit();
+                w.connect().connected()
+            });
+        }
+
+        // 1 MHz PWM clock; one PWM period = `PWM_COUNTERTOP` microseconds
+        pwm.prescaler.write(|w| w.prescaler().div_16());
+        pwm.mode.write(|w| w.updown().up());
+        pwm.countertop
+            .write(|w| unsafe { w.countertop().bits(PWM_COUNTERTOP) });
+        pwm.loop_.write(|w| w.cnt().disabled());
+        // one duty cycle value per LED
+        pwm.decoder.write(|w| {
+            w.load().individual();
+            w.mode().refresh_count()
+        });
+        pwm.seq0
+            .ptr
+            .write(|w| unsafe { w.ptr().bits(duty.as_ptr() as u32) });
+        pwm.seq0
+            .cnt
+            .write(|w| unsafe { w.cnt().bits(duty.len() as u16) });
+        pwm.seq0.refresh.write(|w| w.cnt().continuous());
+        pwm.seq0.enddelay.reset();
+        pwm.enable.write(|w| w.enable().enabled());
+
+        log::debug!("PWM0 configured to drive the LEDs");
+
+        let [duty1, duty2, duty3, duty4] = duty;
+        let leds = DimmableLeds {
+            _1: DimmableLed::new(self._1, duty1),
+            _2: DimmableLed::new(self._2, duty2),
+            _3: DimmableLed::new(self._3, duty3),
+            _4: DimmableLed::new(self._4, duty4),
+            pwm,
+        };
+        restart_pwm();
+        leds
+    }
+}
+
+// a PWM period of 1 millisecond is short enough to not be visible to the human eye
+const PWM_COUNTERTOP: u16 = 1_000;
+
+/// All LEDs on the board, with their brightness controlled by the PWM0 peripheral
+pub struct DimmableLeds {
+    /// LED1: pin P0.13, green LED
+    pub _1: DimmableLed,
+    /// LED2: pin P0.14, green LED
+    pub _2: DimmableLed,
+    /// LED3: pin P0.15, green LED
+    pub _3: DimmableLed,
+    /// LED4: pin P0.16, green LED
+    pub _4: DimmableLed,
+    pwm: PWM0,
+}
+
+impl DimmableLeds {
+    /// Turns off all the LEDs and returns the LEDs and the PWM0 peripheral
+    pub fn release(self) -> (Leds, PWM0) {
+        self.pwm.tasks_stop.write(|w| w.tasks_stop().set_bit());
+        while self.pwm.events_stopped.read().bits() == 0 {
+            continue;
+        }
+        self.pwm.events_stopped.reset();
+        self.pwm.enable.write(|w| w.enable().disabled());
+        for out in self.pwm.psel.out.iter() {
+            out.write(|w| w.connect().disconnected());
+        }
+
+        let mut leds = Leds {
+            _1: self._1.led,
+            _2: self._2.led,
+            _3: self._3.led,
+            _4: self._4.led,
+        };
+        leds._1.off();
+        leds._2.off();
+        leds._3.off();
+        leds._4.off();
+        (leds, self.pwm)
+    }
+}
+
+/// A single LED, with its brightness controlled by the PWM0 peripheral; see `Leds::into_dimmable`
+pub struct DimmableLed {
+    led: Led,
+    // duty cycle value of this LED in the sequence played by the PWM peripheral
+    duty: &'static mut u16,
+    brightness: u8,
+}
+
+impl DimmableLed {
+    fn new(led: Led, duty: &'static mut u16) -> Self {
+        Self {
+            led,
+            duty,
+            brightness: 0,
+        }
+    }
+
+    /// Sets the brightness of the LED; 0 turns the LED off and 255 turns it fully on
+    pub fn set_brightness(&mut self, brightness: u8) {
+        log::trace!(
+            "setting P0.{} (LED) brightness to {}",
+            self.led.inner.pin,
+            brightness
+        );
+
+        // NOTE with bit 15 (POLARITY) cleared the pin is driven low, i.e. the LED is on, from the
+        // start of the PWM period until the counter reaches the duty cycle value
+        let on_time = u32::from(brightness) * u32::from(PWM_COUNTERTOP) / u32::from(u8::MAX);
+        *self.duty = on_time as u16;
+        self.brightness = brightness;
+        restart_pwm();
+    }
+
+    /// Returns the current brightness of the LED
+    pub fn brightness(&self) -> u8 {
+        self.brightness
+    }
+
+    /// Gradually changes the brightness of the LED, from its current value to `brightness`, over
+    /// the given `duration`; blocks until the fade is over
+    pub fn fade_to(&mut self, brightness: u8, duration: Duration, timer: &mut Timer) {
+        let from = i32::from(self.brightness);
+        let to = i32::from(brightness);
+        // one step per brightness level
+        let steps = cmp::max(1, (to - from).abs());
+        let step_duration = duration / steps as u32;
+
+        for step in 1..=steps {
+            self.set_brightness((from + (to - from) * step / steps) as u8);
+            timer.wait(step_duration);
+        }
+    }
+
+    /// Fades the LED in, from off to fully on, and then out again over the given `period`; blocks
+    /// until the LED is off again
+    ///
+    /// Call this method in a loop to make the LED "breathe"
+    pub fn breathe(&mut self, period: Duration, timer: &mut Timer) {
+        self.set_brightness(0);
+        self.fade_to(u8::MAX, period / 2, timer);
+        self.fade_to(0, period / 2, timer);
+    }
+}
+
+// (re)starts the playback of the sequence so the PWM peripheral picks up the new duty cycles
+fn restart_pwm() {
+    // the duty cycle buffer is read by the PWM peripheral using DMA
+    atomic::compiler_fence(Ordering::Release);
+    // NOTE(unsafe) PWM0 is owned by `DimmableLeds`; the SEQSTART task can be triggered from any
+    // context
+    unsafe { &*PWM0::ptr() }.tasks_seqstart[0].write(|w| w.tasks_seqstart().set_bit());
+}
+
+/// All buttons on the board
+pub struct Buttons {
+    /// BUTTON1: pin P0.11
+    pub _1: Button,
+    /// BUTTON2: pin P0.12
+    pub _2: Button,
+    /// BUTTON3: pin P0.24
+    pub _3: Button,
+    /// BUTTON4: pin P0.25
+    pub _4: Button,
+}
+
+/// A single button
+///
+/// The methods of this type debounce the button: a press or release is only reported once the pin
+/// level has been stable for `DEBOUNCE_TIME`. The button state is sampled when these methods are
+/// called so the button must be polled, at least every few milliseconds, for them to notice short
+/// presses.
+///
+/// To be notified of button presses through interrupts, rather than by polling, see the `gpiote`
+/// module
+pub struct Button {
+    inner: Pin<Input<PullUp>>,
+    // debounced state; `true` if pressed
+    pressed: bool,
+    // state of the pin at the last sample and the time at which it was first observed
+    raw: bool,
+    since: Duration,
+    // edges that have not yet been reported by `was_pressed` / `was_released`
+    press_pending: bool,
+    release_pending: bool,
+}
+
+/// Time the button must stay in a new state before the change is reported
+pub const DEBOUNCE_TIME: Duration = Duration::from_millis(10);
+
+impl Button {
+    fn new(inner: Pin<Input<PullUp>>) -> Self {
+        Self {
+            inner,
+            pressed: false,
+            raw: false,
+            since: Duration::from_secs(0),
+            press_pending: false,
+            release_pending: false,
+        }
+    }
+
+    /// Returns `true` if the button is being pressed
+    pub fn is_pressed(&mut self) -> bool {
+        self.update();
+        self.pressed
+    }
+
+    /// Returns `true` if the button is not being pressed
+    pub fn is_released(&mut self) -> bool {
+        !self.is_pressed()
+    }
+
+    /// Returns `true` if the button has been pressed since the last call to this method
+    pub fn was_pressed(&mut self) -> bool {
+        self.update();
+        let pending = self.press_pending;
+        self.press_pending = false;
+        pending
+    }
+
+    /// Returns `true` if the button has been released since the last call to this method
+    pub fn was_released(&mut self) -> bool {
+        self.update();
+        let pending = self.release_pending;
+        self.release_pending = false;
+        pending
+    }
+
+    // samples the pin and updates the debounced state
+    fn update(&mut self) {
+        // NOTE the button pulls the pin low when pressed
+        let raw = self.inner.is_low() == Ok(true);
+        let now = uptime();
+
+        if raw != self.raw {
+            self.raw = raw;
+            self.since = now;
+        } else if raw != self.pressed && now - self.since >= DEBOUNCE_TIME {
+            self.pressed = raw;
+            if raw {
+                log::trace!("button on P0.{} pressed", self.inner.pin);
+                self.press_pending = true;
+            } else {
+                log::trace!("button on P0.{} released", self.inner.pin);
+                self.release_pending = true;
+            }
+        }
+    }
+}
+
+/// NOTE these methods read the level of the pin; the pin is low while the button is pressed
+impl InputPin for Button {
+    type Error = Void;
+
+    fn is_high(&self) -> Result<bool, Void> {
+        self.inner.is_high()
+    }
+
+    fn is_low(&self) -> Result<bool, Void> {
+        self.inner.is_low()
+    }
+}
+
 /// A timer for creating blocking delays
 pub struct Timer {
     inner: hal::Timer<hal::target::TIMER0, OneShot>,
@@ -156,6 +642,130 @@ impl Timer {
 
         log::trace!("... DONE");
     }
+
+    /// Turns this timer into a periodic timer that calls the `tick` function every `interval`
+    ///
+    /// `tick` runs in the `TIMER0` interrupt handler, which is defined by this crate when the
+    /// `periodic-timer` feature is enabled, so it preempts the main loop. Use
+    /// `PeriodicTimer::stop` to get the `Timer` back
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `interval` is zero or longer than `u32::MAX` microseconds
+    #[cfg(feature = "periodic-timer")]
+    pub fn start_periodic(self, interval: Duration, tick: fn()) -> PeriodicTimer {
+        let micros = interval.as_micros();
+        assert!(
+            micros != 0 && micros <= u128::from(u32::MAX),
+            "invalid timer interval"
+        );
+        log::debug!("starting periodic timer ({:?})", interval);
+
+        // NOTE(unsafe) the TIMER0 interrupt is masked
+        unsafe { TICK = Some(tick) }
+        TICKS.store(0, Ordering::Relaxed);
+
+        let mut inner = self.inner.into_periodic();
+        inner.enable_interrupt();
+        // 1 cycle = 1 microsecond
+        inner.start(micros as u32);
+        // NOTE(unsafe) `TICK` is set; the handler doesn't break any critical section
+        unsafe { NVIC::unmask(Interrupt::TIMER0) }
+
+        PeriodicTimer { inner }
+    }
+}
+
+/// A timer that calls a function at a fixed interval; see `Timer::start_periodic`
+#[cfg(feature = "periodic-timer")]
+pub struct PeriodicTimer {
+    inner: hal::Timer<hal::target::TIMER0, Periodic>,
+}
+
+// function called by the TIMER0 interrupt handler
+// NOTE(static mut) only written while the TIMER0 interrupt is masked
+#[cfg(feature = "periodic-timer")]
+static mut TICK: Option<fn()> = None;
+// number of periods elapsed since the periodic timer was started
+#[cfg(feature = "periodic-timer")]
+static TICKS: AtomicU32 = AtomicU32::new(0);
+
+#[cfg(feature = "periodic-timer")]
+impl PeriodicTimer {
+    /// Returns the number of periods elapsed since the timer was started
+    pub fn ticks(&self) -> u32 {
+        TICKS.load(Ordering::Relaxed)
+    }
+
+    /// Stops the timer and turns it back into a (one-shot) `Timer`
+    pub fn stop(self) -> Timer {
+        NVIC::mask(Interrupt::TIMER0);
+
+        let mut inner = self.inner;
+        inner.disable_interrupt();
+        // NOTE this operations returns a `Result` but never returns the `Err` variant
+        let _ = inner.cancel();
+        NVIC::unpend(Interrupt::TIMER0);
+
+        log::debug!("periodic timer stopped");
+
+        Timer {
+            inner: inner.into_oneshot(),
+        }
+    }
+}
+
+impl DelayMs<u32> for Timer {
+    fn delay_ms(&mut self, ms: u32) {
+        self.wait(Duration::from_millis(ms.into()))
+    }
+}
+
+impl DelayMs<u16> for Timer {
+    fn delay_ms(&mut self, ms: u16) {
+        self.wait(Duration::from_millis(ms.into()))
+    }
+}
+
+impl DelayMs<u8> for Timer {
+    fn delay_ms(&mut self, ms: u8) {
+        self.wait(Duration::from_millis(ms.into()))
+    }
+}
+
+impl DelayUs<u32> for Timer {
+    fn delay_us(&mut self, us: u32) {
+        self.wait(Duration::from_micros(us.into()))
+    }
+}
+
+impl DelayUs<u16> for Timer {
+    fn delay_us(&mut self, us: u16) {
+        self.wait(Duration::from_micros(us.into()))
+    }
+}
+
+impl DelayUs<u8> for Timer {
+    fn delay_us(&mut self, us: u8) {
+        self.wait(Duration::from_micros(us.into()))
+    }
+}
+
+/// NOTE the `Time` unit is microseconds; the `CountDown::wait` method is shadowed by
+/// `Timer::wait` so it must be called using the `CountDown::wait(&mut timer)` syntax
+impl CountDown for Timer {
+    type Time = u32;
+
+    fn start<T>(&mut self, microseconds: T)
+    where
+        T: Into<u32>,
+    {
+        self.inner.start(microseconds)
+    }
+
+    fn wait(&mut self) -> nb::Result<(), Void> {
+        self.inner.wait()
+    }
 }
 
 impl ops::Deref for Timer {
@@ -172,96 +782,272 @@ impl ops::DerefMut for Timer {
     }
 }
 
+/// A low power timer for creating delays
+///
+/// Unlike `Timer`, this timer puts the processor to sleep while waiting. It is clocked by the 32.768
+/// kHz low frequency clock (LFCLK) so its resolution is about 30 microseconds.
+pub struct Rtc {
+    inner: RTC1,
+}
+
+impl Rtc {
+    /// Sleeps for at least the specified `duration`
+    ///
+    /// Durations shorter than 2 ticks (61 microseconds) are rounded up to 2 ticks
+    pub fn wait(&mut self, duration: Duration) {
+        log::trace!("sleeping for {:?} ...", duration);
+
+        // 1 tick = 1 / 32768 seconds; round up to not wake up too early
+        const TICKS_IN_ONE_SEC: u64 = 1 << 15;
+        const NANOS_IN_ONE_SEC: u64 = 1_000_000_000;
+        let mut ticks = duration.as_secs() * TICKS_IN_ONE_SEC
+            + (u64::from(duration.subsec_nanos()) * TICKS_IN_ONE_SEC + NANOS_IN_ONE_SEC - 1)
+                / NANOS_IN_ONE_SEC;
+
+        // the COUNTER register is 24-bit wide; stay well below the point where it overflows
+        const MAX_TICKS: u64 = 1 << 23;
+        while ticks > MAX_TICKS {
+            self.sleep(MAX_TICKS as u32);
+            ticks -= MAX_TICKS;
+        }
+        // writing COUNTER + 1 into a CC register may not trigger a COMPARE event
+        const MIN_TICKS: u64 = 2;
+        self.sleep(ticks.max(MIN_TICKS) as u32);
+
+        log::trace!("... DONE");
+    }
+
+    fn sleep(&mut self, ticks: u32) {
+        let rtc = &self.inner;
+
+        rtc.tasks_clear.write(|w| w.tasks_clear().set_bit());
+        rtc.cc[0].write(|w| unsafe { w.compare().bits(ticks) });
+        rtc.events_compare[0].reset();
+        // the interrupt is unmasked in the peripheral but not in the NVIC: the event will pend the
+        // RTC1 interrupt, which wakes up the processor from WFE (because SEVONPEND is set), without
+        // running an interrupt handler
+        rtc.intenset.write(|w| w.compare0().set_bit());
+        rtc.tasks_start.write(|w| w.tasks_start().set_bit());
+
+        sleep_until(|| rtc.events_compare[0].read().bits() != 0);
+
+        rtc.tasks_stop.write(|w| w.tasks_stop().set_bit());
+        rtc.intenclr.write(|w| w.compare0().set_bit());
+        rtc.events_compare[0].reset();
+        NVIC::unpend(Interrupt::RTC1);
+    }
+}
+
 /// Initializes the board
 ///
 /// This return an `Err`or if called more than once
 pub fn init() -> Result<Board, ()> {
-    if let (Some(mut core), Some(periph)) = (
-        cortex_m::Peripherals::take(),
-        hal::target::Peripherals::take(),
-    ) {
-        // NOTE(static mut) this branch runs at most once
-        #[cfg(feature = "advanced")]
-        static mut EP0IN_BUF: [u8; 64] = [0; 64];
-        #[cfg(feature = "beginner")]
-        static mut CLOCKS: Option<
-            Clocks<clocks::ExternalOscillator, clocks::ExternalOscillator, clocks::LfOscStarted>,
-        > = None;
+    if let (Some(core), Some(periph)) = (cortex_m::Peripherals::take(), Peripherals::take()) {
+        Ok(init_rtic(core, periph))
+    } else {
+        Err(())
+    }
+}
 
-        // NOTE this must be executed as early as possible or the tool will timeout
-        // NOTE the unsafety of this macro is incorrect; it must be run at most once
-        #[cfg(feature = "beginner")]
-        rtt_init_print!(BlockIfFull, 16384);
-        #[cfg(feature = "advanced")]
-        rtt_init_print!(NoBlockSkip, 16384);
+/// Initializes the board using peripherals that have already been taken
+///
+/// This is meant for RTIC applications, which take the peripherals before `init` runs: declare
+/// the application with `#[rtic::app(device = dk, peripherals = true)]` and pass `cx.core` and
+/// `cx.device` to this function
+pub fn init_rtic(mut core: cortex_m::Peripherals, periph: Peripherals) -> Board {
+    // NOTE(static mut) this function runs at most once because it takes ownership of the
+    // peripherals, which are singletons
+    #[cfg(feature = "advanced")]
+    static mut EP0IN_BUF: [u8; usbd::EP0IN_CAPACITY] = [0; usbd::EP0IN_CAPACITY];
+    #[cfg(feature = "beginner")]
+    static mut CLOCKS: Option<
+        Clocks<clocks::ExternalOscillator, clocks::ExternalOscillator, clocks::LfOscStarted>,
+    > = None;
 
-        log::set_logger(&Logger).unwrap();
+    // NOTE this must be executed as early as possible or the tool will timeout
+    // NOTE the unsafety of this macro is incorrect; it must be run at most once
+    #[cfg(all(feature = "beginner", not(feature = "defmt")))]
+    rtt_init_print!(BlockIfFull, 16384);
+    #[cfg(all(feature = "advanced", not(feature = "defmt")))]
+    rtt_init_print!(NoBlockSkip, 16384);
 
-        // if not configured in the application we default to the `Info` level
-        if log::max_level() == LevelFilter::Off {
-            log::set_max_level(LevelFilter::Info)
-        }
+    log::set_logger(&Logger).unwrap();
 
-        log::debug!("Initializing the board");
+    // if not configured in the application we default to the `Info` level
+    if log::max_level() == LevelFilter::Off {
+        log::set_max_level(LevelFilter::Info)
+    }
 
-        let clocks = Clocks::new(periph.CLOCK);
-        let clocks = clocks.enable_ext_hfosc();
-        let clocks = clocks.set_lfclk_src_external(clocks::LfOscConfiguration::NoExternalNoBypass);
-        let clocks = clocks.start_lfclk();
-        let _clocks = clocks.enable_ext_hfosc();
-        // extend lifetime to `'static`
-        #[cfg(feature = "beginner")]
-        let clocks = unsafe { CLOCKS.get_or_insert(_clocks) };
+    log::debug!("Initializing the board");
 
-        log::debug!("Clocks configured");
+    let clocks = Clocks::new(periph.CLOCK);
+    let clocks = clocks.enable_ext_hfosc();
+    let clocks = clocks.set_lfclk_src_external(clocks::LfOscConfiguration::NoExternalNoBypass);
+    let clocks = clocks.start_lfclk();
+    let _clocks = clocks.enable_ext_hfosc();
+    // extend lifetime to `'static`
+    #[cfg(feature = "beginner")]
+    let clocks = unsafe { CLOCKS.get_or_insert(_clocks) };
 
-        let mut rtc = Rtc::new(periph.RTC0);
-        rtc.enable_interrupt(RtcInterrupt::Overflow, Some(&mut core.NVIC));
-        rtc.enable_counter();
+    log::debug!("Clocks configured");
 
-        log::debug!("RTC started");
+    let mut rtc = rtc::Rtc::new(periph.RTC0);
+    rtc.enable_interrupt(RtcInterrupt::Overflow, Some(&mut core.NVIC));
+    rtc.enable_counter();
 
-        let pins = p0::Parts::new(periph.P0);
+    log::debug!("RTC started");
 
-        // NOTE LEDs turn on when the pin output level is low
-        let _1 = pins.p0_13.degrade().into_push_pull_output(Level::High);
-        let _2 = pins.p0_14.degrade().into_push_pull_output(Level::High);
-        let _3 = pins.p0_15.degrade().into_push_pull_output(Level::High);
-        let _4 = pins.p0_16.degrade().into_push_pull_output(Level::High);
+    // run RTC1 at the full LFCLK frequency (32.768 kHz)
+    periph
+        .RTC1
+        .prescaler
+        .write(|w| unsafe { w.prescaler().bits(0) });
+    // SEVONPEND: pending interrupts, even disabled ones, wake up the processor from WFE
+    const SCB_SCR_SEVONPEND: u32 = 1 << 4;
+    unsafe { core.SCB.scr.modify(|scr| scr | SCB_SCR_SEVONPEND) }
 
-        log::debug!("I/O pins have been configured for digital output");
+    let pins = p0::Parts::new(periph.P0);
 
-        let timer = hal::Timer::new(periph.TIMER0);
+    // NOTE LEDs turn on when the pin output level is low
+    let _1 = pins.p0_13.degrade().into_push_pull_output(Level::High);
+    let _2 = pins.p0_14.degrade().into_push_pull_output(Level::High);
+    let _3 = pins.p0_15.degrade().into_push_pull_output(Level::High);
+    let _4 = pins.p0_16.degrade().into_push_pull_output(Level::High);
 
-        #[cfg(feature = "beginner")]
-        let radio = {
-            let mut radio = ieee802154::Radio::init(periph.RADIO, clocks);
+    log::debug!("I/O pins have been configured for digital output");
 
-            // set TX power to its maximum value
-            radio.set_txpower(ieee802154::TxPower::Pos8dBm);
-            log::debug!("Radio initialized and configured with TX power set to the maximum value");
-            radio
-        };
+    // NOTE buttons have no external pull-up resistor; the internal one keeps the line high
+    let btn1 = pins.p0_11.degrade().into_pullup_input();
+    let btn2 = pins.p0_12.degrade().into_pullup_input();
+    let btn3 = pins.p0_24.degrade().into_pullup_input();
+    let btn4 = pins.p0_25.degrade().into_pullup_input();
 
-        Ok(Board {
-            leds: Leds {
-                _1: Led { inner: _1 },
-                _2: Led { inner: _2 },
-                _3: Led { inner: _3 },
-                _4: Led { inner: _4 },
-            },
-            #[cfg(feature = "beginner")]
-            radio,
-            timer: Timer { inner: timer },
-            #[cfg(feature = "advanced")]
-            usbd: periph.USBD,
-            #[cfg(feature = "advanced")]
-            power: periph.POWER,
-            #[cfg(feature = "advanced")]
-            ep0in: unsafe { Ep0In::new(&mut EP0IN_BUF) },
-        })
-    } else {
-        Err(())
+    log::debug!("I/O pins have been configured for digital input");
+
+    let pins1 = p1::Parts::new(periph.P1);
+
+    let timer = hal::Timer::new(periph.TIMER0);
+
+    #[cfg(feature = "beginner")]
+    let radio = {
+        let mut radio = ieee802154::Radio::init(periph.RADIO, clocks);
+
+        // set TX power to its maximum value
+        radio.set_txpower(ieee802154::TxPower::Pos8dBm);
+        log::debug!("Radio initialized and configured with TX power set to the maximum value");
+        radio::Radio::new(radio)
+    };
+
+    Board {
+        leds: Leds {
+            _1: Led { inner: _1 },
+            _2: Led { inner: _2 },
+            _3: Led { inner: _3 },
+            _4: Led { inner: _4 },
+        },
+        buttons: Buttons {
+            _1: Button::new(btn1),
+            _2: Button::new(btn2),
+            _3: Button::new(btn3),
+            _4: Button::new(btn4),
+        },
+        gpiote: periph.GPIOTE,
+        pwm: periph.PWM0,
+        #[cfg(feature = "beginner")]
+        radio,
+        timer: Timer { inner: timer },
+        rtc: Rtc { inner: periph.RTC1 },
+        watchdog: Watchdog::new(periph.WDT),
+        pins: Pins {
+            p0_02: pins.p0_02,
+            p0_03: pins.p0_03,
+            p0_04: pins.p0_04,
+            p0_05: pins.p0_05,
+            p0_06: pins.p0_06,
+            p0_07: pins.p0_07,
+            p0_08: pins.p0_08,
+            p0_09: pins.p0_09,
+            p0_10: pins.p0_10,
+            p0_17: pins.p0_17,
+            p0_19: pins.p0_19,
+            p0_20: pins.p0_20,
+            p0_21: pins.p0_21,
+            p0_22: pins.p0_22,
+            p0_23: pins.p0_23,
+            p0_26: pins.p0_26,
+            p0_27: pins.p0_27,
+            p0_28: pins.p0_28,
+            p0_29: pins.p0_29,
+            p0_30: pins.p0_30,
+            p0_31: pins.p0_31,
+            p1_00: pins1.p1_00,
+            p1_01: pins1.p1_01,
+            p1_02: pins1.p1_02,
+            p1_03: pins1.p1_03,
+            p1_04: pins1.p1_04,
+            p1_05: pins1.p1_05,
+            p1_06: pins1.p1_06,
+            p1_07: pins1.p1_07,
+            p1_08: pins1.p1_08,
+            p1_09: pins1.p1_09,
+            p1_10: pins1.p1_10,
+            p1_11: pins1.p1_11,
+            p1_12: pins1.p1_12,
+            p1_13: pins1.p1_13,
+            p1_14: pins1.p1_14,
+            p1_15: pins1.p1_15,
+        },
+        unused: Unused {
+            AAR: periph.AAR,
+            ACL: periph.ACL,
+            CCM: periph.CCM,
+            CC_HOST_RGF: periph.CC_HOST_RGF,
+            COMP: periph.COMP,
+            CRYPTOCELL: periph.CRYPTOCELL,
+            ECB: periph.ECB,
+            EGU0: periph.EGU0,
+            EGU1: periph.EGU1,
+            EGU2: periph.EGU2,
+            EGU3: periph.EGU3,
+            EGU4: periph.EGU4,
+            EGU5: periph.EGU5,
+            I2S: periph.I2S,
+            LPCOMP: periph.LPCOMP,
+            MWU: periph.MWU,
+            NFCT: periph.NFCT,
+            NVMC: periph.NVMC,
+            PDM: periph.PDM,
+            PPI: periph.PPI,
+            PWM1: periph.PWM1,
+            PWM2: periph.PWM2,
+            PWM3: periph.PWM3,
+            QDEC: periph.QDEC,
+            QSPI: periph.QSPI,
+            #[cfg(not(feature = "beginner"))]
+            RADIO: periph.RADIO,
+            RNG: periph.RNG,
+            RTC2: periph.RTC2,
+            SAADC: periph.SAADC,
+            SPIM1: periph.SPIM1,
+            SPIM2: periph.SPIM2,
+            SPIM3: periph.SPIM3,
+            TIMER1: periph.TIMER1,
+            TIMER2: periph.TIMER2,
+            TIMER3: periph.TIMER3,
+            TIMER4: periph.TIMER4,
+            TWIM0: periph.TWIM0,
+            TWIM1: periph.TWIM1,
+            UARTE0: periph.UARTE0,
+            UARTE1: periph.UARTE1,
+            #[cfg(not(feature = "advanced"))]
+            USBD: periph.USBD,
+        },
+        #[cfg(feature = "advanced")]
+        usbd: periph.USBD,
+        #[cfg(feature = "advanced")]
+        power: periph.POWER,
+        #[cfg(feature = "advanced")]
+        ep0in: unsafe { Ep0In::new(&mut EP0IN_BUF) },
     }
 }
 
@@ -277,17 +1063,76 @@ impl Log for Logger {
             return;
         }
 
+        #[cfg(not(feature = "defmt"))]
         rprintln!(
             "{}:{} -- {}",
             record.level(),
             record.target(),
             record.args()
         );
+
+        // `defmt` can't format `fmt::Arguments` so the message is formatted on the device
+        #[cfg(feature = "defmt")]
+        {
+            let mut line = Line::new();
+            let _ = fmt::write(&mut line, *record.args());
+            let (target, line) = (record.target(), line.as_str());
+            match record.level() {
+                log::Level::Error => defmt::error!("{:str} -- {:str}", target, line),
+                log::Level::Warn => defmt::warn!("{:str} -- {:str}", target, line),
+                log::Level::Info => defmt::info!("{:str} -- {:str}", target, line),
+                log::Level::Debug => defmt::debug!("{:str} -- {:str}", target, line),
+                log::Level::Trace => defmt::trace!("{:str} -- {:str}", target, line),
+            }
+        }
     }
 
     fn flush(&self) {}
 }
 
+// A log message; messages longer than the buffer are truncated
+#[cfg(feature = "defmt")]
+struct Line {
+    buffer: [u8; 128],
+    len: usize,
+}
+
+#[cfg(feature = "defmt")]
+impl Line {
+    fn new() -> Self {
+        Self {
+            buffer: [0; 128],
+            len: 0,
+        }
+    }
+
+    fn as_str(&self) -> &str {
+        // NOTE(unsafe) `write_str` only copies whole UTF-8 characters
+        unsafe { core::str::from_utf8_unchecked(&self.buffer[..self.len]) }
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl fmt::Write for Line {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut end = core::cmp::min(s.len(), self.buffer.len() - self.len);
+        while !s.is_char_boundary(end) {
+            end -= 1;
+        }
+
+        self.buffer[self.len..self.len + end].copy_from_slice(&s.as_bytes()[..end]);
+        self.len += end;
+        Ok(())
+    }
+}
+
+// timestamp of the `defmt` logs, in microseconds
+#[cfg(feature = "defmt")]
+#[defmt::timestamp]
+fn timestamp() -> u64 {
+    uptime().as_micros() as u64
+}
+
 // Counter of OVERFLOW events -- an OVERFLOW occurs every (1<<24) ticks
 static OVERFLOWS: AtomicU32 = AtomicU32::new(0);
 
@@ -301,6 +1146,24 @@ fn RTC0() {
     unsafe { core::mem::transmute::<_, RTC0>(()).events_ovrflw.reset() }
 }
 
+// NOTE this interrupt is only unmasked while a `PeriodicTimer` is running
+#[cfg(feature = "periodic-timer")]
+#[interrupt]
+fn TIMER0() {
+    let curr = TICKS.load(Ordering::Relaxed);
+    TICKS.store(curr + 1, Ordering::Relaxed);
+
+    // clear the EVENT register
+    unsafe {
+        core::mem::transmute::<_, hal::target::TIMER0>(()).events_compare[0].reset();
+    }
+
+    // NOTE(unsafe) `TICK` is not modified while this interrupt is unmasked
+    if let Some(tick) = unsafe { TICK } {
+        tick()
+    }
+}
+
 /// Exits the application and prints a backtrace when the program is executed through the `probe-run`
 /// Cargo runner
 pub fn exit() -> ! {
@@ -312,6 +1175,50 @@ pub fn exit() -> ! {
     }
 }
 
+/// Puts the processor to sleep until `condition` returns `true`
+///
+/// The processor wakes up, and re-evaluates `condition`, whenever an interrupt becomes pending --
+/// this includes interrupts that are not enabled in the NVIC -- or an event is signaled (see the SEV
+/// instruction). The typical `condition` checks whether a peripheral EVENT register has been set;
+/// for the peripheral to wake up the processor the corresponding interrupt must be enabled in the
+/// peripheral's INTEN register.
+///
+/// NOTE this relies on configuration done by `dk::init`; calling this function before `dk::init`
+/// may sleep forever
+pub fn sleep_until(mut condition: impl FnMut() -> bool) {
+    while !condition() {
+        asm::wfe();
+    }
+}
+
+/// Turns off the system; this is the deepest power saving mode
+///
+/// Pressing any of the `wakeup_buttons` will wake up, which is to say *reset*, the system. The
+/// contents of RAM are not retained.
+///
+/// NOTE when a debugger is connected (e.g. when running the program with `probe-run`) System OFF is
+/// only emulated and the current consumption will be much higher than in the real System OFF mode
+pub fn system_off(wakeup_buttons: &[&Button]) -> ! {
+    for button in wakeup_buttons {
+        // NOTE(unsafe) we only modify the configuration of pins owned by the `Button`s
+        unsafe { &*hal::target::P0::ptr() }.pin_cnf[usize::from(button.inner.pin)]
+            .modify(|_, w| w.sense().low());
+    }
+
+    log::info!("entering System OFF mode");
+    // force any pending memory operation to complete before the system turns off
+    atomic::compiler_fence(Ordering::SeqCst);
+
+    unsafe { &*hal::target::POWER::ptr() }
+        .systemoff
+        .write(|w| w.systemoff().enter());
+
+    // execution only continues if System OFF is being emulated
+    loop {
+        asm::wfe()
+    }
+}
+
 /// Returns the time elapsed since the call to the `dk::init` function
 ///
 /// The clock that is read to compute this value has a resolution of 30 microseconds.
@@ -358,3 +1265,30 @@ pub fn uptime() -> Duration {
         (((ticks % 32768).wrapping_mul(78125) >> 2).wrapping_mul(5) >> 2).wrapping_mul(5) >> 2;
     Duration::new(secs, nanos as u32)
 }
+
+/// Measures the temperature of the nRF52840 die
+///
+/// The returned value is in units of 0.25 degrees Celsius; divide it by 4 to get whole degrees.
+///
+/// This function blocks for about 36 microseconds, with interrupts disabled, while the measurement
+/// is taken
+pub fn temperature() -> i32 {
+    // NOTE the TEMP peripheral is not handed out by `dk::init` so this function is its only user;
+    // the critical section prevents concurrent measurements from interrupt handlers
+    cortex_m::interrupt::free(|_| unsafe {
+        let temp = core::mem::transmute::<_, hal::target::TEMP>(());
+
+        temp.tasks_start.write(|w| w.tasks_start().set_bit());
+        while temp.events_datardy.read().bits() == 0 {
+            continue;
+        }
+        temp.events_datardy.reset();
+
+        // the TEMP register holds a two's complement value
+        let value = temp.temp.read().bits() as i32;
+        temp.tasks_stop.write(|w| w.tasks_stop().set_bit());
+
+        log::trace!("die temperature: {}/4 C", value);
+        value
+    })
+}
@@ -0,0 +1,204 @@
+//! IEEE 802.15.4 MAC frames
+//!
+//! The radio API sends and receives PHY packets; this module adds the MAC layer header on top of
+//! the `Packet` payload so the frames can be understood by other 802.15.4 devices and by protocol
+//! analyzers like Wireshark. The MAC footer (FCS) is the CRC that the radio hardware computes.
+//!
+//! Only frames with short (16-bit) addresses within a single PAN (PAN ID compression) and frames
+//! without addresses (e.g. acknowledgments) are supported.
+//!
+//! NOTE radio groups (see `Radio::set_group`) add a non-standard header in front of the MAC frame;
+//! don't use them when communicating with other 802.15.4 devices
+
+use crate::ieee802154::Packet;
+
+/// The broadcast short address / PAN ID
+pub const BROADCAST: u16 = 0xffff;
+
+/// MAC frame type
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FrameType {
+    /// Beacon frame
+    Beacon,
+    /// Data frame
+    Data,
+    /// Acknowledgment frame
+    Ack,
+    /// MAC command frame
+    Command,
+}
+
+/// Addressing fields of a MAC header
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Addressing {
+    /// PAN (Personal Area Network) identifier shared by the destination and the source
+    pub pan_id: u16,
+    /// Short address of the destination; use `BROADCAST` to address all the devices in the PAN
+    pub dst: u16,
+    /// Short address of the source
+    pub src: u16,
+}
+
+/// MAC header
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Header {
+    /// Frame type
+    pub frame_type: FrameType,
+    /// Whether the receiver is requested to acknowledge the frame
+    pub ack_request: bool,
+    /// Sequence number
+    pub sequence: u8,
+    /// Addressing fields; `None` for frames without addresses
+    pub addressing: Option<Addressing>,
+}
+
+/// Error returned by `Header::parse`
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error {
+    /// The packet is shorter than the header it announces
+    Truncated,
+    /// The frame uses a feature not supported by this module (e.g. extended addresses or security)
+    Unsupported,
+}
+
+// Frame Control Field (FCF)
+const FCF_FRAME_TYPE_MASK: u16 = 0b111;
+const FCF_SECURITY: u16 = 1 << 3;
+const FCF_ACK_REQUEST: u16 = 1 << 5;
+const FCF_PAN_ID_COMPRESSION: u16 = 1 << 6;
+const FCF_DST_ADDR_MODE_OFFSET: u16 = 10;
+const FCF_SRC_ADDR_MODE_OFFSET: u16 = 14;
+const ADDR_MODE_MASK: u16 = 0b11;
+const ADDR_MODE_NONE: u16 = 0b00;
+const ADDR_MODE_SHORT: u16 = 0b10;
+
+// FCF (2 bytes) + sequence number (1 byte)
+const BASE_LEN: usize = 3;
+// PAN ID (2 bytes) + destination address (2 bytes) + source address (2 bytes)
+const ADDRESSING_LEN: usize = 6;
+
+impl Header {
+    /// Returns the header of a data frame sent from `src` to `dst` within the `pan_id` PAN
+    pub fn data(sequence: u8, pan_id: u16, dst: u16, src: u16) -> Self {
+        Self {
+            frame_type: FrameType::Data,
+            ack_request: false,
+            sequence,
+            addressing: Some(Addressing { pan_id, dst, src }),
+        }
+    }
+
+    /// Returns the header of the frame that acknowledges the frame with the given `sequence`
+    /// number
+    pub fn ack(sequence: u8) -> Self {
+        Self {
+            frame_type: FrameType::Ack,
+            ack_request: false,
+            sequence,
+            addressing: None,
+        }
+    }
+
+    /// Returns the size of this header in bytes
+    fn len(&self) -> usize {
+        if self.addressing.is_some() {
+            BASE_LEN + ADDRESSING_LEN
+        } else {
+            BASE_LEN
+        }
+    }
+
+    /// Writes a frame made up of this header followed by `payload` into the given `packet`
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the frame does not fit in the `packet`
+    pub fn write(&self, packet: &mut Packet, payload: &[u8]) {
+        let hlen = self.len();
+        let len = hlen + payload.len();
+        assert!(
+            len <= usize::from(Packet::CAPACITY),
+            "MAC frame does not fit in a packet"
+        );
+        packet.set_len(len as u8);
+
+        let frame_type = match self.frame_type {
+            FrameType::Beacon => 0,
+            FrameType::Data => 1,
+            FrameType::Ack => 2,
+            FrameType::Command => 3,
+        };
+        let mut fcf = frame_type;
+        if self.ack_request {
+            fcf |= FCF_ACK_REQUEST;
+        }
+        if self.addressing.is_some() {
+            fcf |= FCF_PAN_ID_COMPRESSION
+                | ADDR_MODE_SHORT << FCF_DST_ADDR_MODE_OFFSET
+                | ADDR_MODE_SHORT << FCF_SRC_ADDR_MODE_OFFSET;
+        }
+
+        // all multi-byte fields are little endian
+        packet[..2].copy_from_slice(&fcf.to_le_bytes());
+        packet[2] = self.sequence;
+        if let Some(addressing) = self.addressing {
+            packet[3..5].copy_from_slice(&addressing.pan_id.to_le_bytes());
+            packet[5..7].copy_from_slice(&addressing.dst.to_le_bytes());
+            packet[7..9].copy_from_slice(&addressing.src.to_le_bytes());
+        }
+        packet[hlen..len].copy_from_slice(payload);
+    }
+
+    /// Parses the MAC frame stored in `packet` and returns its header and its payload
+    pub fn parse(packet: &Packet) -> Result<(Self, &[u8]), Error> {
+        let frame: &[u8] = packet;
+        if frame.len() < BASE_LEN {
+            return Err(Error::Truncated);
+        }
+
+        let fcf = u16::from_le_bytes([frame[0], frame[1]]);
+        let frame_type = match fcf & FCF_FRAME_TYPE_MASK {
+            0 => FrameType::Beacon,
+            1 => FrameType::Data,
+            2 => FrameType::Ack,
+            3 => FrameType::Command,
+            _ => return Err(Error::Unsupported),
+        };
+        if fcf & FCF_SECURITY != 0 {
+            return Err(Error::Unsupported);
+        }
+
+        let dst_mode = (fcf >> FCF_DST_ADDR_MODE_OFFSET) & ADDR_MODE_MASK;
+        let src_mode = (fcf >> FCF_SRC_ADDR_MODE_OFFSET) & ADDR_MODE_MASK;
+        let pan_id_compression = fcf & FCF_PAN_ID_COMPRESSION != 0;
+        let addressing = match (dst_mode, src_mode, pan_id_compression) {
+            (ADDR_MODE_NONE, ADDR_MODE_NONE, false) => None,
+
+            (ADDR_MODE_SHORT, ADDR_MODE_SHORT, true) => {
+                if frame.len() < BASE_LEN + ADDRESSING_LEN {
+                    return Err(Error::Truncated);
+                }
+
+                Some(Addressing {
+                    pan_id: u16::from_le_bytes([frame[3], frame[4]]),
+                    dst: u16::from_le_bytes([frame[5], frame[6]]),
+                    src: u16::from_le_bytes([frame[7], frame[8]]),
+                })
+            }
+
+            _ => return Err(Error::Unsupported),
+        };
+
+        let header = Self {
+            frame_type,
+            ack_request: fcf & FCF_ACK_REQUEST != 0,
+            sequence: frame[2],
+            addressing,
+        };
+        Ok((header, &frame[header.len()..]))
+    }
+}
@@ -0,0 +1,91 @@
+//! Panic handler that keeps the panic message in RAM across resets
+
+use core::{
+    cmp, fmt,
+    fmt::Write as _,
+    mem::MaybeUninit,
+    panic::PanicInfo,
+    ptr, str,
+    sync::atomic::{self, Ordering},
+};
+
+use cortex_m::{asm, interrupt};
+
+// marks a valid `Dump`
+const MAGIC: u32 = 0x0bad_c0de;
+
+// size of the message buffer, in bytes; longer messages are truncated
+const CAPACITY: usize = 1024;
+
+#[repr(C)]
+struct Dump {
+    magic: u32,
+    len: usize,
+    buffer: [u8; CAPACITY],
+}
+
+// NOTE `.uninit` is not zeroed by the runtime so the contents of `DUMP` survive a reset (but not a
+// power cycle)
+#[link_section = ".uninit.dk.PANIC_DUMP"]
+static mut DUMP: MaybeUninit<Dump> = MaybeUninit::uninit();
+
+/// Returns the message of the panic that happened before the last reset, if any
+///
+/// The message is only returned once: this function returns `None` on the following calls
+pub fn get_last_panic() -> Option<&'static str> {
+    // NOTE(unsafe) raw pointer accesses to `DUMP`, which is only modified by the panic handler,
+    // that never returns
+    unsafe {
+        let dump = DUMP.as_mut_ptr();
+        if ptr::read_volatile(&(*dump).magic) != MAGIC {
+            return None;
+        }
+        ptr::write_volatile(&mut (*dump).magic, 0);
+
+        // the magic value says the panic handler initialized the rest of `DUMP`
+        let dump = &*dump;
+        let bytes = &dump.buffer[..cmp::min(dump.len, CAPACITY)];
+
+        // the message may have been truncated in the middle of a character
+        Some(match str::from_utf8(bytes) {
+            Ok(s) => s,
+            Err(e) => str::from_utf8_unchecked(&bytes[..e.valid_up_to()]),
+        })
+    }
+}
+
+// writes as much of the message as fits in the buffer
+struct Writer<'a> {
+    dump: &'a mut Dump,
+}
+
+impl fmt::Write for Writer<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let len = self.dump.len;
+        let n = cmp::min(s.len(), CAPACITY - len);
+        self.dump.buffer[len..len + n].copy_from_slice(&s.as_bytes()[..n]);
+        self.dump.len += n;
+        Ok(())
+    }
+}
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    interrupt::disable();
+
+    log::error!("{}", info);
+
+    // NOTE(unsafe) interrupts are disabled and this function never returns so there are no other
+    // references to `DUMP`
+    let dump = unsafe { &mut *DUMP.as_mut_ptr() };
+    dump.magic = 0;
+    dump.len = 0;
+    let _ = write!(Writer { dump }, "{}", info);
+    // make sure the message is complete before it's marked valid
+    atomic::compiler_fence(Ordering::SeqCst);
+    dump.magic = MAGIC;
+
+    loop {
+        asm::bkpt()
+    }
+}
@@ -0,0 +1,138 @@
+//! LED patterns; see `Leds::play`
+
+use core::{str::Chars, time::Duration};
+
+/// A step of an LED pattern
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Step {
+    /// LEDs that are on during this step: bit `0` is LED1, bit `1` is LED2 and so on
+    pub leds: u8,
+    /// How long this step lasts
+    pub duration: Duration,
+}
+
+/// Parses a pattern written in a small language
+///
+/// A pattern is a list of whitespace-separated steps that each last `unit`. A step lists the LEDs
+/// (`1` to `4`) that are on during the step; `_` turns all the LEDs off. For example, `"1 2 3 4"`
+/// turns on one LED after the other and `"13 24 _"` blinks the LEDs diagonally
+///
+/// # Panics
+///
+/// The returned iterator panics if the pattern contains a character that's not `1`, `2`, `3`, `4`
+/// or `_`
+pub fn parse(pattern: &str, unit: Duration) -> impl Iterator<Item = Step> + '_ {
+    pattern.split_whitespace().map(move |step| {
+        let leds = step.chars().fold(0, |leds, c| match c {
+            '1'..='4' => leds | 1 << (c as u8 - b'1'),
+            '_' => leds,
+            _ => panic!("pattern: invalid character {:?}", c),
+        });
+
+        Step {
+            leds,
+            duration: unit,
+        }
+    })
+}
+
+/// Encodes `text` in morse code, with the `leds` bitmask (see `Step`) as the light source
+///
+/// `unit` is the duration of a dot. Characters other than letters, digits and spaces are skipped
+pub fn morse(text: &str, leds: u8, unit: Duration) -> Morse<'_> {
+    Morse {
+        chars: text.chars(),
+        symbols: b"",
+        gap: 0,
+        leds,
+        unit,
+    }
+}
+
+/// Morse code pattern; see `morse`
+pub struct Morse<'a> {
+    chars: Chars<'a>,
+    // the dots and dashes of the current character that have not been sent yet
+    symbols: &'static [u8],
+    // silence, in units, to insert before the next symbol
+    gap: u32,
+    leds: u8,
+    unit: Duration,
+}
+
+impl Iterator for Morse<'_> {
+    type Item = Step;
+
+    fn next(&mut self) -> Option<Step> {
+        loop {
+            if self.gap != 0 {
+                let units = self.gap;
+                self.gap = 0;
+                return Some(Step {
+                    leds: 0,
+                    duration: self.unit * units,
+                });
+            }
+
+            if let Some((symbol, rest)) = self.symbols.split_first() {
+                self.symbols = rest;
+                // 1 unit between the symbols of a character; 3 units between characters
+                self.gap = if rest.is_empty() { 3 } else { 1 };
+                let units = if *symbol == b'.' { 1 } else { 3 };
+                return Some(Step {
+                    leds: self.leds,
+                    duration: self.unit * units,
+                });
+            }
+
+            match self.chars.next()? {
+                // 7 units between words; the previous character already added 3
+                ' ' => self.gap = 4,
+                c => self.symbols = code(c),
+            }
+        }
+    }
+}
+
+// International Morse code
+fn code(c: char) -> &'static [u8] {
+    match c.to_ascii_uppercase() {
+        'A' => b".-",
+        'B' => b"-...",
+        'C' => b"-.-.",
+        'D' => b"-..",
+        'E' => b".",
+        'F' => b"..-.",
+        'G' => b"--.",
+        'H' => b"....",
+        'I' => b"..",
+        'J' => b".---",
+        'K' => b"-.-",
+        'L' => b".-..",
+        'M' => b"--",
+        'N' => b"-.",
+        'O' => b"---",
+        'P' => b".--.",
+        'Q' => b"--.-",
+        'R' => b".-.",
+        'S' => b"...",
+        'T' => b"-",
+        'U' => b"..-",
+        'V' => b"...-",
+        'W' => b".--",
+        'X' => b"-..-",
+        'Y' => b"-.--",
+        'Z' => b"--..",
+        '0' => b"-----",
+        '1' => b".----",
+        '2' => b"..---",
+        '3' => b"...--",
+        '4' => b"....-",
+        '5' => b".....",
+        '6' => b"-....",
+        '7' => b"--...",
+        '8' => b"---..",
+        '9' => b"----.",
+        _ => b"",
+    }
+}
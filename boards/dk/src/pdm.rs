@@ -0,0 +1,162 @@
+//! PDM (Pulse Density Modulation) microphone interface
+
+use core::sync::atomic::{self, Ordering};
+
+use hal::{
+    gpio::{Floating, Input, Output, Pin, PushPull},
+    target::PDM,
+};
+
+/// Number of samples in each of the buffers returned by `Pdm::read`
+pub const BUFFER_LEN: usize = 256;
+
+/// Sample rate, in Hz (PDM clock of 1.032 MHz with a decimation ratio of 64)
+pub const SAMPLE_RATE: u32 = 16_125;
+
+/// Interface to a PDM microphone (mono)
+///
+/// Samples are stored, using EasyDMA, in two buffers of `BUFFER_LEN` samples: while the
+/// application processes one of them the hardware fills the other one.
+pub struct Pdm {
+    inner: PDM,
+    buffers: &'static mut [[i16; BUFFER_LEN]; 2],
+    // index of the buffer that is being filled by the hardware
+    current: usize,
+    running: bool,
+}
+
+impl Pdm {
+    /// Configures the PDM peripheral to sample a microphone connected to the given pins
+    ///
+    /// The PDM peripheral can be obtained from the `unused` field of the `Board`; the pins from its
+    /// `pins` field (e.g. `pins.p0_03.into_push_pull_output(Level::Low).degrade()`)
+    pub fn new(pdm: PDM, clk: Pin<Output<PushPull>>, din: Pin<Input<Floating>>) -> Self {
+        // NOTE(static mut) `PDM` is a singleton and `Pdm` owns it so at most one reference to
+        // these buffers exists at any time
+        static mut BUFFERS: [[i16; BUFFER_LEN]; 2] = [[0; BUFFER_LEN]; 2];
+        let buffers = unsafe { &mut BUFFERS };
+
+        pdm.psel.clk.write(|w| {
+            unsafe { w.pin().bits(clk.pin) };
+            w.port().bit(clk.port);
+            w.connect().connected()
+        });
+        pdm.psel.din.write(|w| {
+            unsafe { w.pin().bits(din.pin) };
+            w.port().bit(din.port);
+            w.connect().connected()
+        });
+
+        pdm.pdmclkctrl.write(|w| w.freq().default());
+        pdm.ratio.write(|w| w.ratio().ratio64());
+        pdm.mode.write(|w| {
+            w.operation().mono();
+            w.edge().left_falling()
+        });
+        pdm.sample
+            .maxcnt
+            .write(|w| unsafe { w.buffsize().bits(BUFFER_LEN as u16) });
+        pdm.enable.write(|w| w.enable().enabled());
+
+        log::debug!(
+            "PDM: CLK = P{}.{}, DIN = P{}.{}",
+            if clk.port { '1' } else { '0' },
+            clk.pin,
+            if din.port { '1' } else { '0' },
+            din.pin
+        );
+
+        Self {
+            inner: pdm,
+            buffers,
+            current: 0,
+            running: false,
+        }
+    }
+
+    /// Starts sampling the microphone
+    ///
+    /// # Panics
+    ///
+    /// This function panics if sampling has already been started
+    pub fn start(&mut self) {
+        assert!(!self.running, "PDM: sampling has already been started");
+
+        self.running = true;
+        self.current = 0;
+        self.set_buffer(0);
+
+        // start DMA transfer
+        atomic::fence(Ordering::Release);
+        self.inner.tasks_start.write(|w| unsafe { w.bits(1) });
+
+        // the first buffer has been captured by the hardware; queue the second one
+        self.wait_started();
+        self.set_buffer(1);
+    }
+
+    /// Waits until a buffer has been filled with samples and returns it
+    ///
+    /// The returned buffer will be filled again by the hardware once the other buffer is full so
+    /// the application must be done with it, and call this method again, within
+    /// `BUFFER_LEN / SAMPLE_RATE` seconds (~16 ms) or samples will be lost
+    ///
+    /// # Panics
+    ///
+    /// This function panics if sampling has not been started with `start`
+    pub fn read(&mut self) -> &[i16] {
+        assert!(self.running, "PDM: sampling has not been started");
+
+        while self.inner.events_end.read().bits() == 0 {
+            continue;
+        }
+        self.inner.events_end.reset();
+        atomic::fence(Ordering::Acquire);
+
+        // the hardware has moved on to the other buffer; queue the one that was just filled
+        let full = self.current;
+        self.current = 1 - full;
+        self.wait_started();
+        self.set_buffer(full);
+
+        &self.buffers[full]
+    }
+
+    /// Stops sampling the microphone
+    pub fn stop(&mut self) {
+        if !self.running {
+            return;
+        }
+        self.running = false;
+
+        self.inner.tasks_stop.write(|w| unsafe { w.bits(1) });
+        while self.inner.events_stopped.read().bits() == 0 {
+            continue;
+        }
+        self.inner.events_stopped.reset();
+        self.inner.events_end.reset();
+        atomic::fence(Ordering::Acquire);
+    }
+
+    /// Releases the PDM peripheral
+    pub fn free(mut self) -> PDM {
+        self.stop();
+        self.inner.enable.write(|w| w.enable().disabled());
+        self.inner
+    }
+
+    fn set_buffer(&mut self, index: usize) {
+        let ptr = self.buffers[index].as_mut_ptr() as u32;
+        self.inner
+            .sample
+            .ptr
+            .write(|w| unsafe { w.sampleptr().bits(ptr) });
+    }
+
+    fn wait_started(&mut self) {
+        while self.inner.events_started.read().bits() == 0 {
+            continue;
+        }
+        self.inner.events_started.reset();
+    }
+}
@@ -1,3 +1,102 @@
 //! Low level access to the nRF52840 peripheral
 
-pub use hal::target::{POWER, USBD};
+#[cfg(not(feature = "beginner"))]
+use hal::target::RADIO;
+use hal::target::{
+    AAR, ACL, CCM, CC_HOST_RGF, COMP, CRYPTOCELL, ECB, EGU0, EGU1, EGU2, EGU3, EGU4, EGU5, I2S,
+    LPCOMP, MWU, NFCT, NVMC, PDM, PPI, PWM1, PWM2, PWM3, QDEC, QSPI, RNG, RTC2, SAADC, SPIM1,
+    SPIM2, SPIM3, TIMER1, TIMER2, TIMER3, TIMER4, TWIM0, TWIM1, UARTE0, UARTE1,
+};
+pub use hal::target::{GPIOTE, POWER, PWM0, USBD};
+
+/// Peripherals that are not used by the `Board`
+///
+/// Peripherals that share their resources with a peripheral in this struct (e.g. `SPIM0`, which
+/// shares them with `TWIM0`) are not included, with the exception of `SPIM1` and `TWIM1`
+#[allow(non_snake_case)]
+pub struct Unused {
+    /// Accelerated Address Resolver
+    pub AAR: AAR,
+    /// Access Control Lists (flash protection)
+    pub ACL: ACL,
+    /// AES CCM mode encryption
+    pub CCM: CCM,
+    /// Host platform interface of the CryptoCell
+    pub CC_HOST_RGF: CC_HOST_RGF,
+    /// Comparator
+    pub COMP: COMP,
+    /// ARM TrustZone CryptoCell 310
+    pub CRYPTOCELL: CRYPTOCELL,
+    /// AES ECB mode encryption
+    pub ECB: ECB,
+    /// Event Generator Unit 0
+    pub EGU0: EGU0,
+    /// Event Generator Unit 1
+    pub EGU1: EGU1,
+    /// Event Generator Unit 2
+    pub EGU2: EGU2,
+    /// Event Generator Unit 3
+    pub EGU3: EGU3,
+    /// Event Generator Unit 4
+    pub EGU4: EGU4,
+    /// Event Generator Unit 5
+    pub EGU5: EGU5,
+    /// Inter-IC Sound interface
+    pub I2S: I2S,
+    /// Low Power Comparator
+    pub LPCOMP: LPCOMP,
+    /// Memory Watch Unit
+    pub MWU: MWU,
+    /// NFC-A compatible radio
+    pub NFCT: NFCT,
+    /// Non-Volatile Memory Controller
+    pub NVMC: NVMC,
+    /// Pulse Density Modulation (digital microphone) interface
+    pub PDM: PDM,
+    /// Programmable Peripheral Interconnect
+    pub PPI: PPI,
+    /// Pulse Width Modulation unit 1
+    pub PWM1: PWM1,
+    /// Pulse Width Modulation unit 2
+    pub PWM2: PWM2,
+    /// Pulse Width Modulation unit 3
+    pub PWM3: PWM3,
+    /// Quadrature Decoder
+    pub QDEC: QDEC,
+    /// External memory interface
+    pub QSPI: QSPI,
+    /// Radio; only available when the `beginner` feature is disabled
+    #[cfg(not(feature = "beginner"))]
+    pub RADIO: RADIO,
+    /// Random Number Generator
+    pub RNG: RNG,
+    /// Real Time Counter 2
+    pub RTC2: RTC2,
+    /// Successive Approximation Analog-to-Digital Converter; see the `adc` module
+    pub SAADC: SAADC,
+    /// SPI master 1; shares its resources with `TWIM1`, only one of the two can be enabled at a time
+    pub SPIM1: SPIM1,
+    /// SPI master 2; see the `spim` module
+    pub SPIM2: SPIM2,
+    /// SPI master 3
+    pub SPIM3: SPIM3,
+    /// Timer 1
+    pub TIMER1: TIMER1,
+    /// Timer 2
+    pub TIMER2: TIMER2,
+    /// Timer 3
+    pub TIMER3: TIMER3,
+    /// Timer 4
+    pub TIMER4: TIMER4,
+    /// I2C master 0; see the `twim` module
+    pub TWIM0: TWIM0,
+    /// I2C master 1; shares its resources with `SPIM1`, only one of the two can be enabled at a time
+    pub TWIM1: TWIM1,
+    /// UART 0; see the `uarte` module
+    pub UARTE0: UARTE0,
+    /// UART 1
+    pub UARTE1: UARTE1,
+    /// USB device; only available when the `advanced` feature is disabled
+    #[cfg(not(feature = "advanced"))]
+    pub USBD: USBD,
+}
@@ -0,0 +1,134 @@
+//! PPI (Programmable Peripheral Interconnect)
+//!
+//! The PPI connects the events of a peripheral (e.g. TIMER compare) to the tasks of another (e.g.
+//! RADIO start) so that the task is triggered by the hardware, without involving the CPU
+
+use hal::target::{generic::Reg, PPI};
+
+/// Number of programmable PPI channels
+pub const CHANNELS: usize = 20;
+
+/// A peripheral event, e.g. `EVENTS_COMPARE[0]` of a TIMER
+#[derive(Clone, Copy, Debug)]
+pub struct Event {
+    address: u32,
+}
+
+impl Event {
+    /// Creates a PPI event from an `EVENTS_*` register (e.g. `&timer.events_compare[0]`)
+    pub fn new<T>(register: &Reg<u32, T>) -> Self {
+        Self {
+            address: register as *const _ as u32,
+        }
+    }
+}
+
+/// A peripheral task, e.g. `TASKS_START` of the RADIO
+#[derive(Clone, Copy, Debug)]
+pub struct Task {
+    address: u32,
+}
+
+impl Task {
+    /// Creates a PPI task from a `TASKS_*` register (e.g. `&radio.tasks_txen`)
+    pub fn new<T>(register: &Reg<u32, T>) -> Self {
+        Self {
+            address: register as *const _ as u32,
+        }
+    }
+}
+
+/// A programmable PPI channel that connects an event to a task
+///
+/// Channels start disabled; use `Ppi::enable` to enable them
+#[derive(Debug)]
+pub struct Channel {
+    index: usize,
+}
+
+impl Channel {
+    /// Returns the index of this channel
+    pub fn index(&self) -> usize {
+        self.index
+    }
+}
+
+/// Allocator of the programmable PPI channels
+pub struct Ppi {
+    inner: PPI,
+    // bit `n` is set if channel `n` is in use
+    used: u32,
+}
+
+impl Ppi {
+    /// Takes control of the PPI peripheral and disables all its channels
+    ///
+    /// The PPI peripheral can be obtained from the `unused` field of the `Board`
+    pub fn new(ppi: PPI) -> Self {
+        ppi.chenclr.write(|w| unsafe { w.bits(!0) });
+
+        Self {
+            inner: ppi,
+            used: 0,
+        }
+    }
+
+    /// Connects `event` to `task` using a free channel; returns the channel
+    ///
+    /// # Panics
+    ///
+    /// This function panics if all the channels are in use
+    pub fn connect(&mut self, event: Event, task: Task) -> Channel {
+        let index = (0..CHANNELS)
+            .find(|i| self.used & (1 << i) == 0)
+            .expect("PPI: all channels are in use");
+        self.used |= 1 << index;
+
+        let ch = &self.inner.ch[index];
+        ch.eep.write(|w| unsafe { w.bits(event.address) });
+        ch.tep.write(|w| unsafe { w.bits(task.address) });
+        self.inner.fork[index].tep.reset();
+
+        log::debug!(
+            "PPI: channel {} connects 0x{:08x} to 0x{:08x}",
+            index,
+            event.address,
+            task.address
+        );
+
+        Channel { index }
+    }
+
+    /// Makes `channel` trigger a second task, `task`, in addition to the one given to `connect`
+    pub fn fork(&mut self, channel: &Channel, task: Task) {
+        self.inner.fork[channel.index]
+            .tep
+            .write(|w| unsafe { w.bits(task.address) });
+    }
+
+    /// Enables `channel`: from now on its event triggers its task(s)
+    pub fn enable(&mut self, channel: &Channel) {
+        self.inner
+            .chenset
+            .write(|w| unsafe { w.bits(1 << channel.index) });
+    }
+
+    /// Disables `channel`
+    pub fn disable(&mut self, channel: &Channel) {
+        self.inner
+            .chenclr
+            .write(|w| unsafe { w.bits(1 << channel.index) });
+    }
+
+    /// Disables `channel` and makes it available to future `connect` calls
+    pub fn disconnect(&mut self, channel: Channel) {
+        self.disable(&channel);
+        self.used &= !(1 << channel.index);
+    }
+
+    /// Releases the PPI peripheral
+    pub fn free(self) -> PPI {
+        self.inner.chenclr.write(|w| unsafe { w.bits(!0) });
+        self.inner
+    }
+}
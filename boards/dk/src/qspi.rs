@@ -0,0 +1,281 @@
+//! QSPI flash memory (MX25R6435F)
+
+use core::{
+    slice,
+    sync::atomic::{self, Ordering},
+};
+
+use hal::{
+    gpio::{
+        p0::{P0_17, P0_19, P0_20, P0_21, P0_22, P0_23},
+        Floating, Input,
+    },
+    target::QSPI,
+};
+
+/// Size of the flash memory, in bytes
+pub const FLASH_SIZE: usize = 8 * 1024 * 1024;
+
+/// Size of the smallest erasable region (sector), in bytes
+pub const SECTOR_SIZE: usize = 4 * 1024;
+
+/// Size of a block, in bytes
+pub const BLOCK_SIZE: usize = 64 * 1024;
+
+// start of the address range the flash is mapped to (execute in place)
+const XIP_START: usize = 0x1200_0000;
+
+// MX25R6435F instructions and status register bits
+const RDSR: u8 = 0x05;
+const WRSR: u8 = 0x01;
+const SR_QE: u8 = 1 << 6;
+
+/// Driver for the 64 Mbit MX25R6435F flash memory connected to the QSPI peripheral
+///
+/// The memory is accessed in quad I/O mode with a clock frequency of 8 MHz.
+///
+/// `read` and `program` use EasyDMA: their buffers must be located in RAM, be 4-byte aligned and
+/// have a length that's a multiple of 4 bytes. The flash address must also be a multiple of 4
+pub struct Qspi {
+    inner: QSPI,
+}
+
+impl Qspi {
+    /// Configures the QSPI peripheral and enables the quad mode of the flash memory
+    ///
+    /// The QSPI peripheral can be obtained from the `unused` field of the `Board`; the pins from
+    /// its `pins` field
+    pub fn new(
+        qspi: QSPI,
+        _csn: P0_17<Input<Floating>>,
+        _sck: P0_19<Input<Floating>>,
+        _io0: P0_20<Input<Floating>>,
+        _io1: P0_21<Input<Floating>>,
+        _io2: P0_22<Input<Floating>>,
+        _io3: P0_23<Input<Floating>>,
+    ) -> Self {
+        qspi.psel
+            .csn
+            .write(|w| unsafe { w.pin().bits(17).port().clear_bit().connect().connected() });
+        qspi.psel
+            .sck
+            .write(|w| unsafe { w.pin().bits(19).port().clear_bit().connect().connected() });
+        qspi.psel
+            .io0
+            .write(|w| unsafe { w.pin().bits(20).port().clear_bit().connect().connected() });
+        qspi.psel
+            .io1
+            .write(|w| unsafe { w.pin().bits(21).port().clear_bit().connect().connected() });
+        qspi.psel
+            .io2
+            .write(|w| unsafe { w.pin().bits(22).port().clear_bit().connect().connected() });
+        qspi.psel
+            .io3
+            .write(|w| unsafe { w.pin().bits(23).port().clear_bit().connect().connected() });
+
+        qspi.xipoffset.reset();
+        qspi.ifconfig0.write(|w| {
+            w.readoc().read4io();
+            w.writeoc().pp4io();
+            w.addrmode()._24bit();
+            w.dpmenable().disable();
+            w.ppsize()._256bytes()
+        });
+        // 32 MHz / (3 + 1) = 8 MHz
+        qspi.ifconfig1
+            .write(|w| unsafe { w.sckdelay().bits(1).spimode().mode0().sckfreq().bits(3) });
+
+        qspi.enable.write(|w| w.enable().enabled());
+        qspi.tasks_activate.write(|w| unsafe { w.bits(1) });
+        let mut qspi = Qspi { inner: qspi };
+        qspi.wait_ready();
+
+        // the quad mode of the memory must be enabled before using quad instructions
+        let status = qspi.custom_instruction(RDSR, 0);
+        if status & SR_QE == 0 {
+            log::debug!("QSPI: enabling quad mode");
+            qspi.custom_instruction(WRSR, status | SR_QE);
+        }
+
+        qspi
+    }
+
+    /// Reads `buffer.len()` bytes starting at `address` into `buffer`
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `address` or `buffer` don't meet the requirements listed in the
+    /// documentation of this type or if the operation goes past the end of the memory
+    pub fn read(&mut self, address: u32, buffer: &mut [u8]) {
+        check_transfer(address, buffer.as_ptr(), buffer.len());
+
+        self.inner
+            .read
+            .src
+            .write(|w| unsafe { w.src().bits(address) });
+        self.inner
+            .read
+            .dst
+            .write(|w| unsafe { w.dst().bits(buffer.as_mut_ptr() as u32) });
+        self.inner
+            .read
+            .cnt
+            .write(|w| unsafe { w.cnt().bits(buffer.len() as u32) });
+
+        // start DMA transfer
+        atomic::fence(Ordering::Release);
+        self.inner.tasks_readstart.write(|w| unsafe { w.bits(1) });
+        self.wait_ready();
+        atomic::fence(Ordering::Acquire);
+    }
+
+    /// Programs `data` into the memory starting at `address`
+    ///
+    /// The region must have been erased first: programming can only change bits from `1` to `0`
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `address` or `data` don't meet the requirements listed in the
+    /// documentation of this type or if the operation goes past the end of the memory
+    pub fn program(&mut self, address: u32, data: &[u8]) {
+        check_transfer(address, data.as_ptr(), data.len());
+
+        self.inner
+            .write
+            .dst
+            .write(|w| unsafe { w.dst().bits(address) });
+        self.inner
+            .write
+            .src
+            .write(|w| unsafe { w.src().bits(data.as_ptr() as u32) });
+        self.inner
+            .write
+            .cnt
+            .write(|w| unsafe { w.cnt().bits(data.len() as u32) });
+
+        // start DMA transfer
+        atomic::fence(Ordering::Release);
+        self.inner.tasks_writestart.write(|w| unsafe { w.bits(1) });
+        self.wait_ready();
+        atomic::fence(Ordering::Acquire);
+
+        log::trace!("QSPI: programmed {}B at 0x{:06x}", data.len(), address);
+    }
+
+    /// Erases (sets to `0xff`) the sector that starts at `address`
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `address` is not a multiple of `SECTOR_SIZE` or is out of bounds
+    pub fn erase_sector(&mut self, address: u32) {
+        check_erase(address, SECTOR_SIZE);
+
+        self.inner
+            .erase
+            .ptr
+            .write(|w| unsafe { w.ptr().bits(address) });
+        self.inner.erase.len.write(|w| w.len()._4kb());
+        self.erase();
+    }
+
+    /// Erases (sets to `0xff`) the block that starts at `address`
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `address` is not a multiple of `BLOCK_SIZE` or is out of bounds
+    pub fn erase_block(&mut self, address: u32) {
+        check_erase(address, BLOCK_SIZE);
+
+        self.inner
+            .erase
+            .ptr
+            .write(|w| unsafe { w.ptr().bits(address) });
+        self.inner.erase.len.write(|w| w.len()._64kb());
+        self.erase();
+    }
+
+    /// Erases (sets to `0xff`) the whole memory
+    ///
+    /// NOTE this operation can take more than one minute
+    pub fn erase_all(&mut self) {
+        self.inner.erase.ptr.reset();
+        self.inner.erase.len.write(|w| w.len().all());
+        self.erase();
+    }
+
+    /// Returns the contents of the memory, mapped into the address space of the microcontroller
+    ///
+    /// Reads from this slice are translated into QSPI read operations by the hardware (execute in
+    /// place, or XIP). They are slower than `read` operations but require no buffer in RAM
+    pub fn xip(&self) -> &[u8] {
+        // NOTE(unsafe) the flash memory is mapped to this address range while the peripheral is
+        // activated; borrowing `self` prevents it from being modified while the slice is alive
+        unsafe { slice::from_raw_parts(XIP_START as *const u8, FLASH_SIZE) }
+    }
+
+    /// Releases the QSPI peripheral
+    pub fn free(self) -> QSPI {
+        self.inner.tasks_deactivate.write(|w| unsafe { w.bits(1) });
+        self.inner.enable.write(|w| w.enable().disabled());
+        self.inner
+    }
+
+    fn erase(&mut self) {
+        self.inner.tasks_erasestart.write(|w| unsafe { w.bits(1) });
+        self.wait_ready();
+
+        log::trace!("QSPI: erase done");
+    }
+
+    // sends a 2-byte custom instruction: the opcode followed by `data`; returns the byte clocked in
+    // while `data` was being clocked out
+    fn custom_instruction(&mut self, opcode: u8, data: u8) -> u8 {
+        // only instructions that modify the memory need to be preceded by WREN
+        let wren = opcode != RDSR;
+
+        self.inner
+            .cinstrdat0
+            .write(|w| unsafe { w.byte0().bits(data) });
+        self.inner.cinstrconf.write(|w| {
+            unsafe { w.opcode().bits(opcode) };
+            w.length()._2b();
+            // keep the WP and HOLD lines high
+            w.lio2().set_bit();
+            w.lio3().set_bit();
+            w.wipwait().bit(wren);
+            w.wren().bit(wren)
+        });
+        self.wait_ready();
+
+        self.inner.cinstrdat0.read().byte0().bits()
+    }
+
+    fn wait_ready(&mut self) {
+        while self.inner.events_ready.read().bits() == 0 {
+            continue;
+        }
+        self.inner.events_ready.reset();
+    }
+}
+
+fn check_transfer(address: u32, ptr: *const u8, len: usize) {
+    assert!(
+        (address as usize | ptr as usize | len) & 0b11 == 0,
+        "QSPI: address, buffer and length must be 4-byte aligned"
+    );
+    assert!(
+        (0x2000_0000..0x2004_0000).contains(&(ptr as usize)),
+        "QSPI: buffer must be located in RAM"
+    );
+    assert!(
+        address as usize + len <= FLASH_SIZE,
+        "QSPI: operation goes past the end of the memory"
+    );
+}
+
+fn check_erase(address: u32, size: usize) {
+    assert!(
+        address as usize & (size - 1) == 0 && (address as usize) < FLASH_SIZE,
+        "QSPI: erase address is not aligned or out of bounds"
+    );
+}
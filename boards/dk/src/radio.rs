@@ -0,0 +1,846 @@
+//! IEEE 802.15.4 radio
+
+use core::{
+    cmp, mem, ops,
+    sync::atomic::{self, Ordering},
+    time::Duration,
+};
+
+use cortex_m::peripheral::NVIC;
+use hal::target::RADIO;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ieee802154::{self, Channel, Error, Packet},
+    Interrupt, Timer,
+};
+
+/// Radio interface
+///
+/// This is a wrapper around the `ieee802154::Radio` driver; all the methods of the driver (e.g.
+/// `set_channel` and `set_txpower`) are available through `Deref`
+///
+/// # Groups
+///
+/// The radio can be made part of a *group* (see `set_group`) to share a channel with other groups
+/// without receiving their packets. When a group is set, `send` prefixes the payload with a
+/// one-byte group header and `recv` / `recv_timeout` discard packets whose header doesn't match
+/// the group, and remove the header from the ones that do. Packets that fail the CRC check are
+/// returned as errors before the group is checked, header included, as their header can't be
+/// trusted. Other send / receive methods of the driver, e.g. `send_no_cca`, bypass the group logic.
+///
+/// Only radios configured with the same group can talk to each other; the Dongle firmware does
+/// not know about groups.
+///
+/// # Large messages
+///
+/// `send_large` splits messages that don't fit in a `Packet` into fragments of up to
+/// `FRAGMENT_LEN` bytes; `recv_large` puts them back together. Each fragment carries a 3-byte
+/// header: message ID, fragment index and index of the last fragment. Lost fragments are not
+/// retransmitted so the loss of any fragment makes the reception of the whole message fail.
+///
+/// # Channel hopping
+///
+/// `hop_sequence` makes the radio cycle through a list of channels, spending a fixed amount of
+/// time (the *dwell* time) on each one. Two radios that follow the same sequence, in sync, can
+/// keep talking to each other while a jammer blocks some of the channels.
+///
+/// # Statistics
+///
+/// The send and receive methods of this type count the packets they handle; `stats` returns the
+/// counters. Packets handled by the methods of the driver (e.g. `send_no_cca`) are not counted.
+///
+/// # Signal strength
+///
+/// `rssi` returns the signal strength of the last packet received by the receive methods of this
+/// type; the LQI (Link Quality Indicator) computed by the hardware is available through
+/// `Packet::lqi`. `energy_detect` measures the energy level of a channel, whatever kind of traffic
+/// it carries, which can be used to find the quietest channel.
+///
+/// # Non-blocking reception
+///
+/// `start_recv` starts listening for a packet and returns right away; `poll_recv` returns the
+/// packet once it has been received. The RADIO interrupt becomes pending when the reception is
+/// over so `poll_recv` can be called from its handler, e.g. an RTIC task bound to `RADIO`.
+pub struct Radio {
+    inner: ieee802154::Radio<'static>,
+    group: Option<u8>,
+    message_id: u8,
+    hopping: Option<Hopping>,
+    stats: Stats,
+    rssi: Option<i8>,
+    // buffer used by `start_recv`
+    rx: &'static mut Packet,
+    receiving: bool,
+}
+
+/// Number of 128-microsecond periods over which `Radio::energy_detect` samples a channel
+pub const ED_PERIODS: u32 = 64;
+
+/// Link statistics returned by `Radio::stats`
+///
+/// The counters wrap around on overflow
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Stats {
+    /// Number of packets sent; each fragment sent by `send_large` counts as one packet
+    pub sent: u32,
+    /// Number of packets received with a valid CRC and accepted
+    pub received: u32,
+    /// Number of packets received with an invalid CRC
+    pub crc_errors: u32,
+    /// Number of packets received with a valid CRC whose group header didn't match the group set
+    /// with `set_group`. These are dropped by the group filter; `recv_raw` returns them but still
+    /// counts them here rather than in `received`
+    pub group_mismatches: u32,
+}
+
+/// Maximum number of channels in a hopping sequence; see `Radio::hop_sequence`
+pub const MAX_HOP_LEN: usize = 16;
+
+struct Hopping {
+    // channel numbers (11 to 26)
+    sequence: [u8; MAX_HOP_LEN],
+    len: usize,
+    dwell: Duration,
+    // position of the current channel in `sequence`
+    index: usize,
+    // time at which the radio moves to the next channel of the sequence
+    next_hop: Duration,
+}
+
+// message ID (1 byte) + fragment index (1 byte) + index of the last fragment (1 byte)
+const FRAGMENT_HEADER_LEN: usize = 3;
+
+/// Maximum payload of a fragment sent by `Radio::send_large`, in bytes
+// one byte is reserved for the group header
+pub const FRAGMENT_LEN: usize = Packet::CAPACITY as usize - 1 - FRAGMENT_HEADER_LEN;
+
+/// Maximum size of a message sent by `Radio::send_large`, in bytes
+pub const MAX_LARGE_LEN: usize = 256 * FRAGMENT_LEN;
+
+/// Error returned by `Radio::recv_large`
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ReassemblyError {
+    /// The message doesn't fit in the buffer
+    BufferTooSmall,
+    /// Some fragments of the message were not received in time
+    Incomplete,
+}
+
+/// Metadata of a frame received with `Radio::recv_raw`
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Frame {
+    /// Length of the frame's payload, in bytes, as reported by the PHY header
+    pub len: u8,
+    /// `true` if the CRC of the frame was valid
+    pub crc_ok: bool,
+    /// CRC of the frame, as computed by the hardware
+    pub crc: u16,
+    /// Link Quality Indicator; this is a measure of the signal strength of the frame. The hardware
+    /// doesn't compute the LQI of frames smaller than 3 bytes
+    pub lqi: Option<u8>,
+    /// Received Signal Strength Indicator, in dBm, sampled at the start of the frame
+    pub rssi: i8,
+}
+
+/// Extension trait that (de)serializes typed values into / from a `Packet`
+///
+/// Values are encoded in the compact [postcard] format. The types must implement serde's
+/// `Serialize` / `Deserialize` traits, which can be derived with `#[derive(Serialize,
+/// Deserialize)]` (requires a `serde` dependency with the `derive` feature). Both ends of the link
+/// must use the same type.
+///
+/// [postcard]: https://docs.rs/postcard
+pub trait PacketExt {
+    /// Serializes `value` into this packet, replacing its contents
+    ///
+    /// Returns an error if the serialized `value` does not fit in a packet; the packet is left
+    /// empty in that case
+    fn serialize<T>(&mut self, value: &T) -> postcard::Result<()>
+    where
+        T: Serialize + ?Sized;
+
+    /// Deserializes a value of type `T` from the contents of this packet
+    fn deserialize<'a, T>(&'a self) -> postcard::Result<T>
+    where
+        T: Deserialize<'a>;
+}
+
+impl PacketExt for Packet {
+    fn serialize<T>(&mut self, value: &T) -> postcard::Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.set_len(Packet::CAPACITY);
+        match postcard::to_slice(value, self).map(|used| used.len()) {
+            Ok(len) => {
+                self.set_len(len as u8);
+                Ok(())
+            }
+            Err(e) => {
+                self.set_len(0);
+                Err(e)
+            }
+        }
+    }
+
+    fn deserialize<'a, T>(&'a self) -> postcard::Result<T>
+    where
+        T: Deserialize<'a>,
+    {
+        postcard::from_bytes(self)
+    }
+}
+
+impl Radio {
+    pub(crate) fn new(inner: ieee802154::Radio<'static>) -> Self {
+        // NOTE(static mut) this function is only called once, by `dk::init`, so at most one
+        // reference to this buffer exists at any time
+        static mut RX: Option<Packet> = None;
+        let rx = unsafe { RX.get_or_insert_with(Packet::new) };
+
+        Self {
+            inner,
+            group: None,
+            message_id: 0,
+            hopping: None,
+            stats: Stats::default(),
+            rssi: None,
+            rx,
+            receiving: false,
+        }
+    }
+
+    // drops the IEEE 802.15.4 driver and returns the RADIO registers, with the radio disabled; this
+    // is used to implement other radio protocols
+    pub(crate) fn into_registers(self) -> RADIO {
+        // the IEEE 802.15.4 driver doesn't hand back the peripheral so we drop it and take over
+        // the RADIO registers
+        let Self { .. } = self;
+        // NOTE(transmute) the only other owner of the RADIO peripheral was dropped above
+        let radio = unsafe { core::mem::transmute::<_, RADIO>(()) };
+
+        // go to a known state
+        radio.shorts.reset();
+        if !radio.state.read().state().is_disabled() {
+            radio.events_disabled.reset();
+            radio.tasks_disable.write(|w| w.tasks_disable().set_bit());
+            while radio.events_disabled.read().bits() == 0 {}
+        }
+        radio.events_ready.reset();
+        radio.events_address.reset();
+        radio.events_end.reset();
+        radio.events_disabled.reset();
+
+        radio
+    }
+
+    /// Makes the radio part of the given `group`; see the type level documentation
+    pub fn set_group(&mut self, group: u8) {
+        log::debug!("radio: joined group {}", group);
+        self.group = Some(group);
+    }
+
+    /// Removes the radio from its group, if any
+    pub fn clear_group(&mut self) {
+        log::debug!("radio: left group");
+        self.group = None;
+    }
+
+    /// Returns the group the radio is part of, if any
+    pub fn group(&self) -> Option<u8> {
+        self.group
+    }
+
+    /// Makes the radio cycle through the channels in `sequence`, spending `dwell` on each one
+    ///
+    /// The radio moves to the first channel of the sequence right away. After that, hops happen
+    /// when the radio is used: the send and receive methods of this type first switch to the
+    /// channel of the current time slot, and `recv`, `recv_timeout` and `poll_recv` keep following
+    /// the sequence while they wait for a packet. `recv_raw` stays on one channel until a frame
+    /// arrives. `set_channel` must not be used while hopping
+    ///
+    /// Two radios can only talk to each other if they use the same sequence and dwell time and
+    /// start hopping at the same time, e.g. right after exchanging a packet on a fixed channel
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `sequence` is empty or longer than `MAX_HOP_LEN`, or if `dwell` is
+    /// zero
+    pub fn hop_sequence(&mut self, sequence: &[Channel], dwell: Duration) {
+        assert!(
+            !sequence.is_empty() && sequence.len() <= MAX_HOP_LEN,
+            "radio: invalid hopping sequence length"
+        );
+        assert!(dwell.as_micros() != 0, "radio: dwell time must not be zero");
+
+        let mut numbers = [0; MAX_HOP_LEN];
+        for (number, channel) in numbers.iter_mut().zip(sequence) {
+            *number = channel_number(channel);
+        }
+
+        self.inner.set_channel(channel(numbers[0]));
+        self.hopping = Some(Hopping {
+            sequence: numbers,
+            len: sequence.len(),
+            dwell,
+            index: 0,
+            next_hop: crate::uptime() + dwell,
+        });
+        log::debug!(
+            "radio: hopping over {} channels every {:?}",
+            sequence.len(),
+            dwell
+        );
+    }
+
+    /// Stops hopping; the radio stays on its current channel
+    pub fn stop_hopping(&mut self) {
+        log::debug!("radio: stopped hopping");
+        self.hopping = None;
+    }
+
+    /// Returns the position of the current channel in the hopping sequence, if hopping
+    pub fn hop_index(&mut self) -> Option<usize> {
+        self.hop();
+        self.hopping.as_ref().map(|hopping| hopping.index)
+    }
+
+    /// Returns the link statistics collected since the radio was initialized or since the last
+    /// call to `reset_stats`
+    pub fn stats(&self) -> Stats {
+        self.stats
+    }
+
+    /// Sets all the link statistics counters to zero
+    pub fn reset_stats(&mut self) {
+        self.stats = Stats::default();
+    }
+
+    /// Returns the RSSI (Received Signal Strength Indicator) of the last packet received by `recv`,
+    /// `recv_timeout` or `recv_raw`, in dBm
+    ///
+    /// The RSSI is sampled by the hardware at the start of the packet, right after its SFD. This
+    /// returns `None` if no packet has been received yet
+    pub fn rssi(&self) -> Option<i8> {
+        self.rssi
+    }
+
+    /// Measures the energy level of the given `channel`
+    ///
+    /// The channel is sampled for `ED_PERIODS` periods of 128 microseconds (~8 ms) and the highest
+    /// level is returned, as reported by the hardware (not in dBm); see the
+    /// `ieee802154::Radio::energy_detection_scan` method. The radio stays on `channel` afterwards,
+    /// or until the next hop if hopping
+    pub fn energy_detect(&mut self, channel: Channel) -> u8 {
+        let number = channel_number(&channel);
+        self.inner.set_channel(channel);
+        let level = self.inner.energy_detection_scan(ED_PERIODS);
+        log::trace!("radio: energy level of channel {} = {}", number, level);
+        level
+    }
+
+    /// Sends the given `packet`
+    ///
+    /// # Panics
+    ///
+    /// When a group is set the group header takes one byte of the packet so this function panics
+    /// if `packet` holds more than `Packet::CAPACITY - 1` bytes
+    pub fn send(&mut self, packet: &Packet) {
+        self.hop();
+        self.stats.sent = self.stats.sent.wrapping_add(1);
+        if let Some(group) = self.group {
+            let mut framed = Packet::new();
+            framed.set_len(packet.len() + 1);
+            framed[0] = group;
+            framed[1..].copy_from_slice(packet);
+            self.inner.send(&framed);
+        } else {
+            self.inner.send(packet);
+        }
+    }
+
+    /// Receives one radio packet and copies its contents into the given `packet` buffer
+    ///
+    /// This methods returns the `Ok` variant if the CRC included the packet was successfully
+    /// validated by the hardware; otherwise it returns the `Err` variant, without checking the
+    /// group and with the group header, if any, left in `packet`. When a group is set packets
+    /// with a valid CRC that belong to other groups are skipped. When hopping, the reception is
+    /// done with `start_recv` and `poll_recv` so the LQI of the packet is not available
+    pub fn recv(&mut self, packet: &mut Packet) -> Result<u16, u16> {
+        if self.hopping.is_some() {
+            // `poll_recv` moves to the next channel at the end of each time slot
+            self.start_recv();
+            loop {
+                match self.poll_recv(packet) {
+                    Ok(crc) => return Ok(crc),
+                    Err(nb::Error::Other(crc)) => return Err(crc),
+                    Err(nb::Error::WouldBlock) => {}
+                }
+            }
+        }
+
+        loop {
+            self.hop();
+            arm_rssi();
+            let res = self.inner.recv(packet);
+            self.read_rssi();
+            if res.is_err() {
+                self.record(false, false);
+                return res;
+            }
+
+            let accepted = self.accept(packet);
+            self.record(true, accepted);
+            if accepted {
+                return res;
+            }
+        }
+    }
+
+    /// Listens for a packet for no longer than the specified amount of microseconds and copies its
+    /// contents into the given `packet` buffer
+    ///
+    /// See the `ieee802154::Radio::recv_timeout` method for details. Packets that fail the CRC
+    /// check are returned as `Error::Crc` without checking their group, like in `recv`. When a
+    /// group is set packets with a valid CRC that belong to other groups are skipped; the time
+    /// spent receiving them counts towards the timeout
+    pub fn recv_timeout(
+        &mut self,
+        packet: &mut Packet,
+        timer: &mut Timer,
+        microseconds: u32,
+    ) -> Result<u16, Error> {
+        let deadline = crate::uptime() + Duration::from_micros(microseconds.into());
+        let mut remaining = microseconds;
+        loop {
+            self.hop();
+            // when hopping, don't listen past the end of the current time slot
+            let slot = cmp::min(u128::from(remaining), self.until_next_hop()) as u32;
+            arm_rssi();
+            let res = self.inner.recv_timeout(packet, timer, slot);
+            match res {
+                Err(Error::Timeout) if slot == remaining => return res,
+                Err(Error::Timeout) => {}
+                Err(Error::Crc(_)) => {
+                    self.read_rssi();
+                    self.record(false, false);
+                    return res;
+                }
+                Ok(_) => {
+                    self.read_rssi();
+                    let accepted = self.accept(packet);
+                    self.record(true, accepted);
+                    if accepted {
+                        return res;
+                    }
+                }
+            }
+
+            remaining = match deadline.checked_sub(crate::uptime()) {
+                Some(remaining) if remaining.as_micros() != 0 => remaining.as_micros() as u32,
+                _ => return Err(Error::Timeout),
+            };
+        }
+    }
+
+    /// Starts listening for a packet in the background; use `poll_recv` to collect it
+    ///
+    /// The END interrupt of the RADIO peripheral is enabled so the RADIO interrupt becomes pending
+    /// when a packet has been received: bind a handler to the interrupt that calls `poll_recv` or
+    /// wait for it with `dk::sleep_until`. No other method of the radio, including those of the
+    /// driver, may be used until `poll_recv` returns a packet or `cancel_recv` is called
+    ///
+    /// # Panics
+    ///
+    /// This function panics if a reception is already in progress
+    pub fn start_recv(&mut self) {
+        assert!(!self.receiving, "radio: a reception is already in progress");
+        self.hop();
+
+        // going through DISABLED makes the radio pick up new settings, e.g. a channel change
+        disable();
+
+        let radio = registers();
+        radio.events_address.reset();
+        radio.events_end.reset();
+        // the PHY header precedes the payload
+        let ptr = self.rx.as_mut_ptr() as u32 - 1;
+        // NOTE(unsafe) the buffer is located in RAM and is not accessed until the reception is over
+        radio
+            .packetptr
+            .write(|w| unsafe { w.packetptr().bits(ptr) });
+        radio.shorts.write(|w| {
+            w.rxready_start().enabled();
+            w.address_rssistart().enabled()
+        });
+        radio.intenset.write(|w| w.end().set_bit());
+        self.receiving = true;
+
+        // start DMA transfer
+        atomic::fence(Ordering::Release);
+        radio.tasks_rxen.write(|w| w.tasks_rxen().set_bit());
+    }
+
+    /// Copies the packet received since the call to `start_recv` into the given `packet` buffer
+    ///
+    /// Returns `WouldBlock` if no packet has been received yet. Otherwise the reception is over and
+    /// the result is the same as the one of `recv`: `Ok` if the CRC of the packet is valid, `Err`
+    /// otherwise, without checking the group. Packets with a valid CRC that belong to other groups
+    /// are skipped. The LQI of the packet is not copied into `packet`; use `rssi` instead
+    ///
+    /// When hopping, the reception moves to the next channel of the sequence at the end of each
+    /// time slot, unless a packet is being received
+    ///
+    /// # Panics
+    ///
+    /// This function panics if no reception is in progress
+    pub fn poll_recv(&mut self, packet: &mut Packet) -> nb::Result<u16, u16> {
+        assert!(self.receiving, "radio: no reception is in progress");
+
+        let radio = registers();
+        if radio.events_end.read().bits() == 0 {
+            if self.until_next_hop() == 1 && radio.events_address.read().bits() == 0 {
+                self.cancel_recv();
+                self.start_recv();
+            }
+            return Err(nb::Error::WouldBlock);
+        }
+        radio.events_end.reset();
+        atomic::fence(Ordering::Acquire);
+        self.finish_recv();
+
+        let crc_ok = radio.crcstatus.read().crcstatus().is_crcok();
+        let crc = radio.rxcrc.read().rxcrc().bits() as u16;
+        self.read_rssi();
+        packet.copy_from_slice(self.rx);
+        if !crc_ok {
+            self.record(false, false);
+            return Err(nb::Error::Other(crc));
+        }
+
+        let accepted = self.accept(packet);
+        self.record(true, accepted);
+        if accepted {
+            Ok(crc)
+        } else {
+            self.start_recv();
+            Err(nb::Error::WouldBlock)
+        }
+    }
+
+    /// Stops the reception started with `start_recv`, if any
+    pub fn cancel_recv(&mut self) {
+        if self.receiving {
+            disable();
+            atomic::fence(Ordering::Acquire);
+            self.finish_recv();
+        }
+    }
+
+    // leaves the registers in a state the driver expects
+    fn finish_recv(&mut self) {
+        let radio = registers();
+        radio.shorts.reset();
+        radio.intenclr.write(|w| w.end().set_bit());
+        NVIC::unpend(Interrupt::RADIO);
+        self.receiving = false;
+    }
+
+    /// Sends `data`, which may be larger than a `Packet`, as a sequence of fragments
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `data` is larger than `MAX_LARGE_LEN` bytes
+    pub fn send_large(&mut self, data: &[u8]) {
+        assert!(data.len() <= MAX_LARGE_LEN, "data is too large");
+
+        let id = self.message_id;
+        self.message_id = id.wrapping_add(1);
+
+        // an empty message is sent as one empty fragment
+        let count = cmp::max(1, (data.len() + FRAGMENT_LEN - 1) / FRAGMENT_LEN);
+        let last = (count - 1) as u8;
+        log::debug!(
+            "radio: sending message {} ({} bytes) in {} fragments",
+            id,
+            data.len(),
+            count
+        );
+
+        let mut packet = Packet::new();
+        for index in 0..count {
+            let start = index * FRAGMENT_LEN;
+            let end = cmp::min(start + FRAGMENT_LEN, data.len());
+            let fragment = &data[start..end];
+
+            packet.set_len((FRAGMENT_HEADER_LEN + fragment.len()) as u8);
+            packet[0] = id;
+            packet[1] = index as u8;
+            packet[2] = last;
+            packet[FRAGMENT_HEADER_LEN..].copy_from_slice(fragment);
+            self.send(&packet);
+        }
+    }
+
+    /// Receives a message sent with `send_large` and copies it into the given `buffer`
+    ///
+    /// This method waits for the first fragment without a timeout; after that each fragment must
+    /// arrive within `microseconds` of the previous one. Fragments that fail the CRC check are
+    /// dropped. If a fragment of a different message arrives the incomplete message is discarded.
+    /// On success the size of the message is returned
+    pub fn recv_large(
+        &mut self,
+        buffer: &mut [u8],
+        timer: &mut Timer,
+        microseconds: u32,
+    ) -> Result<usize, ReassemblyError> {
+        let mut packet = Packet::new();
+        // (message ID, index of the last fragment) of the message being reassembled
+        let mut current = None;
+        // one bit per fragment
+        let mut received = [0u32; 8];
+        let mut len = 0;
+
+        loop {
+            let res = if current.is_some() {
+                self.recv_timeout(&mut packet, timer, microseconds)
+            } else {
+                self.recv(&mut packet).map_err(Error::Crc)
+            };
+            match res {
+                Ok(_) => {}
+                Err(Error::Crc(_)) => continue,
+                Err(Error::Timeout) => {
+                    log::debug!("radio: timed out waiting for a fragment");
+                    return Err(ReassemblyError::Incomplete);
+                }
+            }
+
+            if usize::from(packet.len()) < FRAGMENT_HEADER_LEN {
+                continue;
+            }
+            let (id, index, last) = (packet[0], packet[1], packet[2]);
+            let fragment = &packet[FRAGMENT_HEADER_LEN..];
+            if index > last || fragment.len() > FRAGMENT_LEN {
+                continue;
+            }
+
+            if current != Some((id, last)) {
+                if current.is_some() {
+                    log::debug!("radio: discarded incomplete message");
+                }
+                current = Some((id, last));
+                received = [0; 8];
+                len = 0;
+            }
+
+            let start = usize::from(index) * FRAGMENT_LEN;
+            let end = start + fragment.len();
+            if end > buffer.len() {
+                return Err(ReassemblyError::BufferTooSmall);
+            }
+            buffer[start..end].copy_from_slice(fragment);
+            received[usize::from(index / 32)] |= 1 << (index % 32);
+            if index == last {
+                len = end;
+            }
+
+            if (0..=last).all(|i| received[usize::from(i / 32)] & (1 << (i % 32)) != 0) {
+                log::debug!("radio: received message {} ({} bytes)", id, len);
+                return Ok(len);
+            }
+        }
+    }
+
+    // moves to the channel of the current time slot, if hopping
+    fn hop(&mut self) {
+        let hopping = if let Some(hopping) = &mut self.hopping {
+            hopping
+        } else {
+            return;
+        };
+
+        let now = crate::uptime();
+        if now < hopping.next_hop {
+            return;
+        }
+
+        // more than one slot may have elapsed since the radio was last used
+        let slots = ((now - hopping.next_hop).as_micros() / hopping.dwell.as_micros()) as u32 + 1;
+        hopping.index = (hopping.index + slots as usize) % hopping.len;
+        hopping.next_hop += hopping.dwell * slots;
+
+        let number = hopping.sequence[hopping.index];
+        self.inner.set_channel(channel(number));
+        log::trace!("radio: hopped to channel {}", number);
+    }
+
+    // returns the number of microseconds until the next hop (at least 1), or `u128::MAX` if not
+    // hopping
+    fn until_next_hop(&self) -> u128 {
+        match &self.hopping {
+            Some(hopping) => match hopping.next_hop.checked_sub(crate::uptime()) {
+                Some(left) => cmp::max(1, left.as_micros()),
+                None => 1,
+            },
+            None => u128::MAX,
+        }
+    }
+
+    // updates the statistics with the outcome of the reception of a packet
+    fn record(&mut self, crc_ok: bool, accepted: bool) {
+        let counter = if !crc_ok {
+            &mut self.stats.crc_errors
+        } else if accepted {
+            &mut self.stats.received
+        } else {
+            &mut self.stats.group_mismatches
+        };
+        *counter = counter.wrapping_add(1);
+    }
+
+    // reads, and returns, the RSSI sampled during the reception of the last packet; see `arm_rssi`
+    fn read_rssi(&mut self) -> i8 {
+        // the register holds the magnitude of the RSSI, which is a negative value
+        let rssi = -(registers().rssisample.read().rssisample().bits() as i8);
+        self.rssi = Some(rssi);
+        rssi
+    }
+
+    // returns `true` if the received `packet` belongs to our group, or no group is set; in that
+    // case the group header is removed from the `packet`
+    fn accept(&self, packet: &mut Packet) -> bool {
+        if self.group.is_none() {
+            return true;
+        }
+
+        if !self.in_group(packet) {
+            log::trace!("radio: dropped packet from another group");
+            return false;
+        }
+
+        let len = packet.len();
+        packet.copy_within(1.., 0);
+        packet.set_len(len - 1);
+        true
+    }
+
+    // returns `true` if the group header of `packet` matches our group, or no group is set
+    fn in_group(&self, packet: &Packet) -> bool {
+        match self.group {
+            Some(group) => !packet.is_empty() && packet[0] == group,
+            None => true,
+        }
+    }
+
+    /// Receives the next frame sent on the current channel, whatever its contents
+    ///
+    /// In IEEE 802.15.4 mode the radio hardware neither filters frames on their (MAC) address nor
+    /// drops frames that fail the CRC check, so every frame sent on the channel is received. Unlike
+    /// `recv`, this method doesn't apply the group filter nor removes the group header: the frame
+    /// is stored in `packet` as it was sent. Check `Frame::crc_ok` before trusting the contents of
+    /// `packet`. This can be used to passively observe the traffic on a channel, e.g. that of other
+    /// groups
+    pub fn recv_raw(&mut self, packet: &mut Packet) -> Frame {
+        self.hop();
+        arm_rssi();
+        let res = self.inner.recv(packet);
+        let rssi = self.read_rssi();
+
+        let len = packet.len();
+        let (crc_ok, crc) = match res {
+            Ok(crc) => (true, crc),
+            Err(crc) => (false, crc),
+        };
+        let lqi = if len >= 3 { Some(packet.lqi()) } else { None };
+        // frames from other groups are received but counted as group mismatches
+        let in_group = self.in_group(packet);
+        self.record(crc_ok, in_group);
+
+        let frame = Frame {
+            len,
+            crc_ok,
+            crc,
+            lqi,
+            rssi,
+        };
+        log::trace!("radio: {:?}", frame);
+        frame
+    }
+}
+
+impl ops::Deref for Radio {
+    type Target = ieee802154::Radio<'static>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl ops::DerefMut for Radio {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.inner
+    }
+}
+
+// the registers of the RADIO peripheral, which is owned by the IEEE 802.15.4 driver
+fn registers() -> &'static hal::target::radio::RegisterBlock {
+    // NOTE(unsafe) the driver is not running while we hold `&mut Radio`; the registers must be left
+    // in a state the driver expects
+    unsafe { &*RADIO::ptr() }
+}
+
+// moves the radio to the DISABLED state
+fn disable() {
+    let radio = registers();
+    if !radio.state.read().state().is_disabled() {
+        radio.events_disabled.reset();
+        radio.tasks_disable.write(|w| w.tasks_disable().set_bit());
+        while radio.events_disabled.read().bits() == 0 {}
+        radio.events_disabled.reset();
+    }
+}
+
+// makes the hardware sample the RSSI of the next received packet, right after its SFD
+//
+// NOTE this overwrites the SHORTS register configured by the driver, enabling the
+// ADDRESS->RSSISTART shortcut on top of the driver's shortcuts. The driver doesn't use shortcuts
+// while receiving and rewrites the register before sending and before an energy detection scan,
+// so the extra shortcut doesn't affect its other operations
+fn arm_rssi() {
+    registers()
+        .shorts
+        .modify(|_, w| w.address_rssistart().enabled());
+}
+
+// `Channel` is not `Copy` so channels are stored as channel numbers
+fn channel(number: u8) -> Channel {
+    match number {
+        11 => Channel::_11,
+        12 => Channel::_12,
+        13 => Channel::_13,
+        14 => Channel::_14,
+        15 => Channel::_15,
+        16 => Channel::_16,
+        17 => Channel::_17,
+        18 => Channel::_18,
+        19 => Channel::_19,
+        20 => Channel::_20,
+        21 => Channel::_21,
+        22 => Channel::_22,
+        23 => Channel::_23,
+        24 => Channel::_24,
+        25 => Channel::_25,
+        _ => Channel::_26,
+    }
+}
+
+fn channel_number(channel: &Channel) -> u8 {
+    (11..26)
+        .find(|number| mem::discriminant(&self::channel(*number)) == mem::discriminant(channel))
+        .unwrap_or(26)
+}
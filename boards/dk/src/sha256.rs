@@ -0,0 +1,15 @@
+//! SHA-256 hashing
+//!
+//! The digest is computed in software by the `sha2` crate; no hardware acceleration is used
+
+use sha2::{Digest, Sha256};
+
+/// Size of a SHA-256 digest, in bytes
+pub const DIGEST_SIZE: usize = 32;
+
+/// Computes the SHA-256 digest of `data`
+pub fn digest(data: &[u8]) -> [u8; DIGEST_SIZE] {
+    let mut digest = [0; DIGEST_SIZE];
+    digest.copy_from_slice(&Sha256::digest(data));
+    digest
+}
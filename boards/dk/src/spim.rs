@@ -0,0 +1,73 @@
+//! SPIM (Serial Peripheral Interface Master) peripheral
+
+use embedded_hal::blocking::spi;
+pub use hal::spim::{Error, Frequency, Mode, MODE_0, MODE_1, MODE_2, MODE_3};
+use hal::{
+    gpio::{Floating, Input, Output, Pin, PushPull},
+    spim::Pins,
+    target::SPIM2,
+};
+
+/// SPI bus master
+///
+/// This bus is not configured by `dk::init`; use `new` to set it up, e.g. on the Arduino header
+/// pins P1.15 (SCK), P1.13 (MOSI) and P1.14 (MISO).
+///
+/// Chip select pins are not managed by this bus; drive them from the application.
+///
+/// Write buffers located in Flash (e.g. string literals) are copied into RAM, in chunks, before
+/// being handed to the peripheral so any slice can be used with the `Write` trait.
+pub struct Spim {
+    inner: hal::spim::Spim<SPIM2>,
+}
+
+impl Spim {
+    /// Configures the SPIM2 peripheral as a SPI bus master on the given pins
+    ///
+    /// `mosi` and `miso` can be omitted if the bus is only used in one direction. The SPIM2
+    /// peripheral can be obtained from the `unused` field of the `Board`; the pins from its `pins`
+    /// field (e.g. `pins.p1_15.degrade().into_push_pull_output(Level::Low)`)
+    pub fn new(
+        spim: SPIM2,
+        sck: Pin<Output<PushPull>>,
+        mosi: Option<Pin<Output<PushPull>>>,
+        miso: Option<Pin<Input<Floating>>>,
+        frequency: Frequency,
+        mode: Mode,
+    ) -> Self {
+        log::debug!(
+            "SPIM2: SCK = P{}.{}",
+            if sck.port { '1' } else { '0' },
+            sck.pin
+        );
+
+        // the over-read character (ORC) is clocked out when the bus reads more bytes than it writes
+        const ORC: u8 = 0xff;
+        Self {
+            inner: hal::spim::Spim::new(spim, Pins { sck, mosi, miso }, frequency, mode, ORC),
+        }
+    }
+
+    /// Releases the SPIM2 peripheral
+    pub fn free(self) -> SPIM2 {
+        self.inner.free()
+    }
+}
+
+impl spi::Transfer<u8> for Spim {
+    type Error = Error;
+
+    fn transfer<'w>(&mut self, words: &'w mut [u8]) -> Result<&'w [u8], Error> {
+        log::trace!("SPI: transfer {}B", words.len());
+        spi::Transfer::transfer(&mut self.inner, words)
+    }
+}
+
+impl spi::Write<u8> for Spim {
+    type Error = Error;
+
+    fn write(&mut self, words: &[u8]) -> Result<(), Error> {
+        log::trace!("SPI: write {}B", words.len());
+        spi::Write::write(&mut self.inner, words)
+    }
+}
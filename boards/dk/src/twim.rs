@@ -0,0 +1,77 @@
+//! TWIM (I2C compatible Two-Wire Interface Master) peripheral
+
+use embedded_hal::blocking::i2c;
+pub use hal::twim::{Error, Frequency};
+use hal::{
+    gpio::{Floating, Input, Pin},
+    target::TWIM0,
+    twim::Pins,
+};
+
+/// I2C bus master
+///
+/// This bus is not configured by `dk::init`; use `new` to set it up, e.g. on the Arduino header
+/// pins P0.27 (SCL) and P0.26 (SDA) at a frequency of 100 kHz.
+///
+/// NOTE buffers passed to the read and write methods must be located in RAM
+pub struct Twim {
+    inner: hal::twim::Twim<TWIM0>,
+}
+
+impl Twim {
+    /// Configures the TWIM0 peripheral as an I2C bus master on the given `scl` and `sda` pins
+    ///
+    /// The TWIM0 peripheral can be obtained from the `unused` field of the `Board`; the pins from
+    /// its `pins` field (e.g. `pins.p0_27.degrade()`)
+    ///
+    /// NOTE the internal pull-up resistors are enabled on both pins
+    pub fn new(
+        twim: TWIM0,
+        scl: Pin<Input<Floating>>,
+        sda: Pin<Input<Floating>>,
+        frequency: Frequency,
+    ) -> Self {
+        log::debug!("TWIM0: SCL = P0.{}, SDA = P0.{}", scl.pin, sda.pin);
+
+        Self {
+            inner: hal::twim::Twim::new(twim, Pins { scl, sda }, frequency),
+        }
+    }
+
+    /// Releases the TWIM0 peripheral
+    pub fn free(self) -> TWIM0 {
+        self.inner.free()
+    }
+}
+
+impl i2c::Write for Twim {
+    type Error = Error;
+
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), Error> {
+        log::trace!("I2C: write {}B to {:#04x}", bytes.len(), address);
+        self.inner.write(address, bytes)
+    }
+}
+
+impl i2c::Read for Twim {
+    type Error = Error;
+
+    fn read(&mut self, address: u8, buffer: &mut [u8]) -> Result<(), Error> {
+        log::trace!("I2C: read {}B from {:#04x}", buffer.len(), address);
+        self.inner.read(address, buffer)
+    }
+}
+
+impl i2c::WriteRead for Twim {
+    type Error = Error;
+
+    fn write_read(&mut self, address: u8, bytes: &[u8], buffer: &mut [u8]) -> Result<(), Error> {
+        log::trace!(
+            "I2C: write {}B then read {}B from {:#04x}",
+            bytes.len(),
+            buffer.len(),
+            address
+        );
+        self.inner.write_then_read(address, bytes, buffer)
+    }
+}
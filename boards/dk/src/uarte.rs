@@ -0,0 +1,127 @@
+//! UARTE (Universal Asynchronous Receiver/Transmitter with EasyDMA) peripheral
+
+use core::fmt;
+
+pub use hal::uarte::{Baudrate, Error, Parity};
+use hal::{
+    gpio::{Floating, Input, Output, Pin, PushPull},
+    target::UARTE0,
+    uarte::Pins,
+};
+
+/// Serial port
+///
+/// This serial port is not configured by `dk::init`; use `new` to set it up. To talk to the host
+/// connect it to the virtual COM port of the on-board J-Link: pins P0.06 (TXD) and P0.08 (RXD). On
+/// the host the port shows up as a USB CDC-ACM device; e.g. `/dev/ttyACM0` on Linux.
+pub struct Uarte {
+    inner: hal::uarte::Uarte<UARTE0>,
+    // the last line was terminated by `\r`; a `\n` that immediately follows must be ignored
+    skip_lf: bool,
+}
+
+// size of the RAM buffer used to send data that may be located in Flash
+const TX_CHUNK_SIZE: usize = 16;
+
+impl Uarte {
+    /// Configures the UARTE0 peripheral as a serial port on the given pins; no hardware flow
+    /// control is used
+    ///
+    /// The UARTE0 peripheral can be obtained from the `unused` field of the `Board`; the pins from
+    /// its `pins` field (e.g. `pins.p0_06.degrade().into_push_pull_output(Level::High)` for TXD and
+    /// `pins.p0_08.degrade()` for RXD)
+    pub fn new(
+        uarte: UARTE0,
+        txd: Pin<Output<PushPull>>,
+        rxd: Pin<Input<Floating>>,
+        parity: Parity,
+        baudrate: Baudrate,
+    ) -> Self {
+        log::debug!("UARTE0: TXD = P0.{}, RXD = P0.{}", txd.pin, rxd.pin);
+
+        let pins = Pins {
+            rxd,
+            txd,
+            cts: None,
+            rts: None,
+        };
+        Self {
+            inner: hal::uarte::Uarte::new(uarte, pins, parity, baudrate),
+            skip_lf: false,
+        }
+    }
+
+    /// Sends all the `bytes` over the serial port; blocks until the transfer is complete
+    ///
+    /// `bytes` does not need to be located in RAM
+    pub fn write(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        log::trace!("UARTE: write {}B", bytes.len());
+
+        // EasyDMA can only read from RAM so copy the data into a stack buffer first
+        let mut buf = [0; TX_CHUNK_SIZE];
+        for chunk in bytes.chunks(TX_CHUNK_SIZE) {
+            buf[..chunk.len()].copy_from_slice(chunk);
+            self.inner.write(&buf[..chunk.len()])?;
+        }
+
+        Ok(())
+    }
+
+    /// Fills the `buffer` with data received over the serial port; blocks until the buffer is full
+    ///
+    /// The buffer must have a length of at most 255 bytes
+    pub fn read(&mut self, buffer: &mut [u8]) -> Result<(), Error> {
+        self.inner.read(buffer)?;
+
+        log::trace!("UARTE: read {}B", buffer.len());
+
+        Ok(())
+    }
+
+    /// Receives a single line of text into the `buffer` and returns its length
+    ///
+    /// The line terminator (`\n`, `\r` or `\r\n`) is not stored in the `buffer`. If the line does
+    /// not fit in the `buffer` then the `buffer` is returned filled and the rest of the line is
+    /// left unread
+    pub fn read_line(&mut self, buffer: &mut [u8]) -> Result<usize, Error> {
+        let mut len = 0;
+        while len < buffer.len() {
+            let mut byte = [0];
+            self.inner.read(&mut byte)?;
+
+            let skip_lf = self.skip_lf;
+            self.skip_lf = false;
+            match byte[0] {
+                // second half of a `\r\n` terminator
+                b'\n' if skip_lf => {}
+
+                b'\r' => {
+                    self.skip_lf = true;
+                    break;
+                }
+
+                b'\n' => break,
+
+                byte => {
+                    buffer[len] = byte;
+                    len += 1;
+                }
+            }
+        }
+
+        log::trace!("UARTE: read line of {}B", len);
+
+        Ok(len)
+    }
+
+    /// Releases the UARTE0 peripheral
+    pub fn free(self) -> UARTE0 {
+        self.inner.free()
+    }
+}
+
+impl fmt::Write for Uarte {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.write(s.as_bytes()).map_err(|_| fmt::Error)
+    }
+}
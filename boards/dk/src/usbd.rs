@@ -1,57 +1,122 @@
 //! USBD peripheral
 
-use core::sync::atomic::{self, Ordering};
+use core::{
+    cmp,
+    num::NonZeroU8,
+    sync::atomic::{self, Ordering},
+};
+
+use hal::target::usbd::{EPIN, EPOUT};
 
 use crate::{
     errata,
     peripheral::{POWER, USBD},
 };
 
+/// Maximum packet size of endpoint 0, in bytes
+pub const EP0_MAX_PACKET_SIZE: usize = 64;
+
+/// Maximum packet size of the bulk and interrupt endpoints (`EpIn` and `EpOut`), in bytes
+pub const EP_MAX_PACKET_SIZE: usize = 64;
+
+/// Maximum amount of data that `Ep0In` can send in a single data stage, in bytes
+pub const EP0IN_CAPACITY: usize = 256;
+
 /// Endpoint IN 0
 pub struct Ep0In {
-    buffer: &'static mut [u8; 64],
+    buffer: &'static mut [u8; EP0IN_CAPACITY],
     busy: bool,
+    // size of the data in `buffer`
+    len: usize,
+    // number of bytes of `buffer` that have been handed to the hardware
+    offset: usize,
+    // whether a zero-length packet must be sent after the data
+    zlp: bool,
 }
 
 impl Ep0In {
     /// # Safety
     /// Must be created at most once (singleton)
-    pub(crate) unsafe fn new(buffer: &'static mut [u8; 64]) -> Self {
+    pub(crate) unsafe fn new(buffer: &'static mut [u8; EP0IN_CAPACITY]) -> Self {
         Self {
             buffer,
             busy: false,
+            len: 0,
+            offset: 0,
+            zlp: false,
         }
     }
 
     /// Starts a data transfer over endpoint 0
     ///
+    /// Data larger than the maximum packet size (64 bytes) is sent as several packets; the `end`
+    /// method sends the next packet
+    ///
     /// # Panics
     ///
     /// - This function panics if the last transfer was not finished by calling the `end` function
-    /// - This function panics if `bytes` is larger than the maximum packet size (64 bytes)
+    /// - This function panics if `bytes` is larger than `EP0IN_CAPACITY`
     pub fn start(&mut self, bytes: &[u8], usbd: &USBD) {
+        self.start_transfer(bytes, false, usbd)
+    }
+
+    /// Responds to a control read request (e.g. GET_DESCRIPTOR) with the given `bytes`
+    ///
+    /// `wlength` is the WLENGTH field of the setup packet. `bytes` is truncated to `wlength` bytes
+    /// and, if the response is shorter than `wlength` and its size is a multiple of the maximum
+    /// packet size, a zero-length packet (ZLP) is sent after it to signal the end of the data
+    ///
+    /// # Panics
+    ///
+    /// See `start`
+    pub fn respond(&mut self, bytes: &[u8], wlength: u16, usbd: &USBD) {
+        let n = cmp::min(bytes.len(), usize::from(wlength));
+        let zlp = n != 0 && n % EP0_MAX_PACKET_SIZE == 0 && n < usize::from(wlength);
+        self.start_transfer(&bytes[..n], zlp, usbd)
+    }
+
+    fn start_transfer(&mut self, bytes: &[u8], zlp: bool, usbd: &USBD) {
         assert!(!self.busy, "EP0IN: last transfer has not completed");
         assert!(
             bytes.len() <= self.buffer.len(),
-            "EP0IN: multi-packet data transfers are not supported"
+            "EP0IN: data transfer is larger than the buffer"
         );
 
         let n = bytes.len();
         self.buffer[..n].copy_from_slice(bytes);
+        self.len = n;
+        self.offset = 0;
+        self.zlp = zlp;
+
+        self.busy = true;
+
+        log::info!("EP0IN: start {}B transfer", n);
+
+        self.start_packet(usbd);
+    }
 
-        // use a "shortcut" to issue a status stage after the data transfer is complete
+    // sends the next packet of the current transfer
+    fn start_packet(&mut self, usbd: &USBD) {
+        let n = cmp::min(self.len - self.offset, EP0_MAX_PACKET_SIZE);
+        let last = if n == 0 {
+            // this is either an empty transfer or the trailing ZLP
+            self.zlp = false;
+            true
+        } else {
+            self.offset + n == self.len && !self.zlp
+        };
+
+        // use a "shortcut" to issue a status stage after the last packet has been sent
         usbd.shorts
-            .modify(|_, w| w.ep0datadone_ep0status().set_bit());
+            .modify(|_, w| w.ep0datadone_ep0status().bit(last));
         usbd.epin0
             .maxcnt
             .write(|w| unsafe { w.maxcnt().bits(n as u8) });
         usbd.epin0
             .ptr
-            .write(|w| unsafe { w.ptr().bits(self.buffer.as_ptr() as u32) });
+            .write(|w| unsafe { w.ptr().bits(self.buffer[self.offset..].as_ptr() as u32) });
 
-        self.busy = true;
-
-        log::info!("EP0IN: start {}B transfer", n);
+        self.offset += n;
 
         // start DMA transfer
         dma_start();
@@ -60,7 +125,9 @@ impl Ep0In {
 
     /// Completes a data transfer
     ///
-    /// This function must be called after the EP0DATADONE event is raised
+    /// This function must be called after the EP0DATADONE event is raised. If the transfer spans
+    /// several packets this function sends the next one; in that case the transfer is complete
+    /// after the last EP0DATADONE event
     ///
     /// # Panics
     ///
@@ -74,9 +141,277 @@ impl Ep0In {
             dma_end();
             usbd.events_ep0datadone.reset();
 
-            self.busy = false;
-            log::info!("EP0IN: transfer done");
+            if self.offset < self.len || self.zlp {
+                log::debug!("EP0IN: {}B left to send", self.len - self.offset);
+                self.start_packet(usbd);
+            } else {
+                self.busy = false;
+                log::info!("EP0IN: transfer done");
+            }
+        }
+    }
+}
+
+/// Performs the data stage of a control write request (host to device), storing the data sent
+/// by the host in `buffer`, and then the status stage
+///
+/// `wlength` is the WLENGTH field of the setup packet. Returns the number of bytes received. This
+/// function blocks until the host has sent all the data
+///
+/// # Panics
+///
+/// This function panics if `buffer` is smaller than `wlength`
+pub fn ep0out_read(usbd: &USBD, buffer: &mut [u8], wlength: u16) -> usize {
+    let wlength = usize::from(wlength);
+    assert!(
+        buffer.len() >= wlength,
+        "EP0OUT: buffer is smaller than the data stage"
+    );
+
+    let mut received = 0;
+    while received < wlength {
+        // allow the host to send the next packet and wait for it
+        usbd.tasks_ep0rcvout
+            .write(|w| w.tasks_ep0rcvout().set_bit());
+        while usbd.events_ep0datadone.read().bits() == 0 {
+            continue;
+        }
+        usbd.events_ep0datadone.reset();
+
+        // copy the packet from the peripheral's internal buffer into `buffer`
+        let n = cmp::min(
+            usize::from(usbd.size.epout[0].read().size().bits()),
+            wlength - received,
+        );
+        usbd.epout0
+            .ptr
+            .write(|w| unsafe { w.ptr().bits(buffer[received..].as_ptr() as u32) });
+        usbd.epout0
+            .maxcnt
+            .write(|w| unsafe { w.maxcnt().bits(n as u8) });
+        dma_start();
+        usbd.tasks_startepout[0].write(|w| w.tasks_startepout().set_bit());
+        while usbd.events_endepout[0].read().bits() == 0 {
+            continue;
+        }
+        usbd.events_endepout[0].reset();
+        dma_end();
+
+        received += n;
+
+        // a short packet ends the data stage
+        if n < EP0_MAX_PACKET_SIZE {
+            break;
+        }
+    }
+
+    log::info!("EP0OUT: received {}B", received);
+
+    usbd.tasks_ep0status
+        .write(|w| w.tasks_ep0status().set_bit());
+
+    received
+}
+
+/// IN endpoint (device to host) used for bulk or interrupt transfers
+///
+/// The USBD peripheral handles bulk and interrupt endpoints in the same way; the transfer type
+/// is the one reported to the host in the endpoint descriptor
+pub struct EpIn {
+    index: usize,
+    // whether the host has yet to read the last packet
+    busy: bool,
+}
+
+impl EpIn {
+    /// Enables IN endpoint `index` (e.g. `1` for endpoint address `0x81`)
+    ///
+    /// This also unmasks the EPDATA interrupt; see `epdata`
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `index` is not in the range `1..=7`
+    pub fn new(index: usize, usbd: &USBD) -> Self {
+        assert!(
+            (1..=7).contains(&index),
+            "EPIN: endpoint index must be in the range 1..=7"
+        );
+
+        usbd.epinen
+            .modify(|r, w| unsafe { w.bits(r.bits() | 1 << index) });
+        usbd.intenset.write(|w| w.epdata().set_bit());
+
+        Self { index, busy: false }
+    }
+
+    /// Returns `true` if the host has not yet read the packet passed to the last `write` call
+    pub fn is_busy(&mut self, usbd: &USBD) -> bool {
+        if self.busy {
+            // EPDATASTATUS bits 1..=7: data acknowledged by the host (write 1 to clear)
+            let mask = 1 << self.index;
+            if usbd.epdatastatus.read().bits() & mask != 0 {
+                usbd.epdatastatus.write(|w| unsafe { w.bits(mask) });
+                self.busy = false;
+            }
+        }
+
+        self.busy
+    }
+
+    /// Hands a packet over to the USBD peripheral; the host will read it in its next IN
+    /// transaction to this endpoint
+    ///
+    /// A packet shorter than `EP_MAX_PACKET_SIZE` ends a bulk transfer
+    ///
+    /// # Panics
+    ///
+    /// - This function panics if the host has not read the last packet (see `is_busy`)
+    /// - This function panics if `bytes` is larger than `EP_MAX_PACKET_SIZE`
+    pub fn write(&mut self, bytes: &[u8], usbd: &USBD) {
+        assert!(
+            !self.is_busy(usbd),
+            "EPIN: last packet has not been read by the host"
+        );
+        assert!(
+            bytes.len() <= EP_MAX_PACKET_SIZE,
+            "EPIN: packet is larger than the maximum packet size"
+        );
+
+        // EasyDMA cannot read from Flash so copy the data into RAM first
+        let mut buffer = [0; EP_MAX_PACKET_SIZE];
+        let n = bytes.len();
+        buffer[..n].copy_from_slice(bytes);
+
+        let epin = epin(usbd, self.index);
+        epin.ptr
+            .write(|w| unsafe { w.ptr().bits(buffer.as_ptr() as u32) });
+        epin.maxcnt.write(|w| unsafe { w.maxcnt().bits(n as u8) });
+
+        // copy the data into the endpoint's buffer
+        dma_start();
+        usbd.tasks_startepin[self.index].write(|w| w.tasks_startepin().set_bit());
+        while usbd.events_endepin[self.index].read().bits() == 0 {
+            continue;
+        }
+        usbd.events_endepin[self.index].reset();
+        dma_end();
+
+        self.busy = true;
+
+        log::debug!("EPIN{}: queued {}B packet", self.index, n);
+    }
+}
+
+/// OUT endpoint (host to device) used for bulk or interrupt transfers
+///
+/// See `EpIn` for a note on transfer types
+pub struct EpOut {
+    index: usize,
+}
+
+impl EpOut {
+    /// Enables OUT endpoint `index` (e.g. `1` for endpoint address `0x01`)
+    ///
+    /// This also unmasks the EPDATA interrupt; see `epdata`
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `index` is not in the range `1..=7`
+    pub fn new(index: usize, usbd: &USBD) -> Self {
+        assert!(
+            (1..=7).contains(&index),
+            "EPOUT: endpoint index must be in the range 1..=7"
+        );
+
+        usbd.epouten
+            .modify(|r, w| unsafe { w.bits(r.bits() | 1 << index) });
+        usbd.intenset.write(|w| w.epdata().set_bit());
+        // writing any value to SIZE.EPOUT lets the host send data to the endpoint
+        usbd.size.epout[index].reset();
+
+        Self { index }
+    }
+
+    /// Copies the packet last sent by the host into `buffer` and returns its size
+    ///
+    /// Returns `None` if no packet has been received since the last call. The host cannot send
+    /// the next packet until this one has been read
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `buffer` is smaller than the received packet
+    pub fn read(&mut self, buffer: &mut [u8], usbd: &USBD) -> Option<usize> {
+        // EPDATASTATUS bits 17..=23: data received from the host (write 1 to clear)
+        let mask = 1 << (16 + self.index);
+        if usbd.epdatastatus.read().bits() & mask == 0 {
+            return None;
+        }
+        usbd.epdatastatus.write(|w| unsafe { w.bits(mask) });
+
+        let n = usize::from(usbd.size.epout[self.index].read().size().bits());
+        assert!(
+            buffer.len() >= n,
+            "EPOUT: buffer is smaller than the received packet"
+        );
+
+        let epout = epout(usbd, self.index);
+        epout
+            .ptr
+            .write(|w| unsafe { w.ptr().bits(buffer.as_ptr() as u32) });
+        epout.maxcnt.write(|w| unsafe { w.maxcnt().bits(n as u8) });
+
+        // copy the packet from the endpoint's buffer into `buffer`
+        dma_start();
+        usbd.tasks_startepout[self.index].write(|w| w.tasks_startepout().set_bit());
+        while usbd.events_endepout[self.index].read().bits() == 0 {
+            continue;
         }
+        usbd.events_endepout[self.index].reset();
+        dma_end();
+
+        log::debug!("EPOUT{}: received {}B packet", self.index, n);
+
+        Some(n)
+    }
+}
+
+/// Clears the EPDATA event and returns `true` if it was set
+///
+/// The EPDATA event is raised when the host reads a packet from an `EpIn` or sends a packet to
+/// an `EpOut`. It is not reported by `next_event` so the USBD interrupt handler must call this
+/// function when using `EpIn` or `EpOut`, and then poll the endpoints
+pub fn epdata(usbd: &USBD) -> bool {
+    if usbd.events_epdata.read().bits() != 0 {
+        usbd.events_epdata.reset();
+        true
+    } else {
+        false
+    }
+}
+
+fn epin(usbd: &USBD, index: usize) -> &EPIN {
+    match index {
+        1 => &usbd.epin1,
+        2 => &usbd.epin2,
+        3 => &usbd.epin3,
+        4 => &usbd.epin4,
+        5 => &usbd.epin5,
+        6 => &usbd.epin6,
+        7 => &usbd.epin7,
+        _ => unreachable!(),
+    }
+}
+
+fn epout(usbd: &USBD, index: usize) -> &EPOUT {
+    match index {
+        1 => &usbd.epout1,
+        2 => &usbd.epout2,
+        3 => &usbd.epout3,
+        4 => &usbd.epout4,
+        5 => &usbd.epout5,
+        6 => &usbd.epout6,
+        7 => &usbd.epout7,
+        _ => unreachable!(),
     }
 }
 
@@ -155,6 +490,127 @@ pub fn init(power: POWER, usbd: &USBD) {
     usbd.usbpullup.write(|w| w.connect().set_bit());
 }
 
+/// USB device state; see section 9.1 of the USB specification
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum State {
+    /// The device has been reset and has no address yet
+    Default,
+
+    /// The host has assigned an address to the device
+    Address(NonZeroU8),
+
+    /// The host has selected a configuration
+    Configured {
+        /// Device address
+        address: NonZeroU8,
+        /// `bConfigurationValue` of the selected configuration
+        value: NonZeroU8,
+    },
+}
+
+// NOTE `defmt::Format` can't be derived because `defmt` doesn't implement it for `NonZeroU8`
+#[cfg(feature = "defmt")]
+impl defmt::Format for State {
+    fn format(&self, f: &mut defmt::Formatter) {
+        match self {
+            State::Default => defmt::write!(f, "Default"),
+            State::Address(address) => defmt::write!(f, "Address({:u8})", address.get()),
+            State::Configured { address, value } => defmt::write!(
+                f,
+                "Configured(address: {:u8}, value: {:u8})",
+                address.get(),
+                value.get()
+            ),
+        }
+    }
+}
+
+/// Error returned when a request is not valid in the current device state
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct InvalidState;
+
+/// Tracks the device state using the state of the USBD peripheral
+///
+/// The device address is not stored but read from the USBADDR register, which the USBD
+/// peripheral updates on its own when it handles a SET_ADDRESS request and clears on a USB reset.
+/// Only the selected configuration is tracked in software
+#[derive(Default)]
+pub struct Device {
+    configuration: Option<NonZeroU8>,
+}
+
+impl Device {
+    /// Returns the current device state
+    pub fn state(&self, usbd: &USBD) -> State {
+        match (usbaddr(usbd), self.configuration) {
+            (None, _) => State::Default,
+            (Some(address), None) => State::Address(address),
+            (Some(address), Some(value)) => State::Configured { address, value },
+        }
+    }
+
+    /// Handles the USBRESET event: returns to the `Default` state and disables all endpoints but
+    /// endpoint 0
+    pub fn reset(&mut self, usbd: &USBD) {
+        self.configuration = None;
+        disable_endpoints(usbd);
+
+        log::debug!("USB: reset; entering the default state");
+    }
+
+    /// Handles a SET_CONFIGURATION request and completes it with a STATUS stage
+    ///
+    /// `value` is the `bConfigurationValue` of the requested configuration; `None` returns the
+    /// device to the `Address` state and disables all endpoints but endpoint 0. Endpoints that are
+    /// part of the configuration must be enabled by the caller (see `EpIn` and `EpOut`)
+    ///
+    /// Returns an error, without completing the request, if the device is in the `Default` state;
+    /// the caller should then stall endpoint 0
+    pub fn set_configuration(
+        &mut self,
+        value: Option<NonZeroU8>,
+        usbd: &USBD,
+    ) -> Result<(), InvalidState> {
+        if usbaddr(usbd).is_none() {
+            // behavior not specified by the USB specification
+            return Err(InvalidState);
+        }
+
+        if value.is_none() {
+            disable_endpoints(usbd);
+        }
+        self.configuration = value;
+
+        log::debug!("USB: {:?}", self.state(usbd));
+
+        usbd.tasks_ep0status
+            .write(|w| w.tasks_ep0status().set_bit());
+
+        Ok(())
+    }
+}
+
+/// Reads the USBADDR register and returns the address assigned to the device by the host, if any
+pub fn usbaddr(usbd: &USBD) -> Option<NonZeroU8> {
+    NonZeroU8::new(usbd.usbaddr.read().addr().bits())
+}
+
+/// Reads the EPSTATUS register
+///
+/// Bits `0..=8` (IN endpoints) and bits `16..=24` (OUT endpoints) are set when the EasyDMA
+/// registers of the corresponding endpoint have been captured by the peripheral. Write `1` to a
+/// bit to clear it
+pub fn epstatus(usbd: &USBD) -> u32 {
+    usbd.epstatus.read().bits()
+}
+
+// disables endpoints 1..=7 but leaves the control endpoint, endpoint 0, enabled
+fn disable_endpoints(usbd: &USBD) {
+    usbd.epinen.modify(|r, w| unsafe { w.bits(r.bits() & 1) });
+    usbd.epouten.modify(|r, w| unsafe { w.bits(r.bits() & 1) });
+}
+
 /// Stalls endpoint 0
 pub fn ep0stall(usbd: &USBD) {
     usbd.tasks_ep0stall.write(|w| w.tasks_ep0stall().set_bit());
@@ -162,6 +618,7 @@ pub fn ep0stall(usbd: &USBD) {
 
 /// USBD.EVENTS registers mapped to an enum
 #[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum Event {
     /// `EVENTS_USBRESET` register was active
     UsbReset,
@@ -0,0 +1,83 @@
+//! WDT (Watchdog Timer) peripheral
+
+use core::time::Duration;
+
+use hal::target::{POWER, WDT};
+
+/// Watchdog timer
+///
+/// Once started, the watchdog must be fed periodically or it will reset the system
+pub struct Watchdog {
+    wdt: WDT,
+}
+
+/// A running watchdog timer
+///
+/// NOTE the watchdog can only be stopped by a reset
+pub struct RunningWatchdog {
+    wdt: WDT,
+}
+
+// the watchdog counter is clocked by the 32.768 kHz low frequency clock
+const TICKS_IN_ONE_SEC: u64 = 1 << 15;
+// the minimum value of the CRV register
+const MIN_TICKS: u64 = 0xf;
+
+impl Watchdog {
+    pub(crate) fn new(wdt: WDT) -> Self {
+        Self { wdt }
+    }
+
+    /// Returns `true` if the last system reset was caused by the watchdog
+    ///
+    /// This flag is cleared by this function so it returns `true` at most once after each reset
+    pub fn caused_last_reset(&self) -> bool {
+        // NOTE(unsafe) RESETREAS is only accessed by this function
+        let power = unsafe { &*POWER::ptr() };
+        let dog = power.resetreas.read().dog().is_detected();
+        if dog {
+            // write 1 to clear the flag
+            power.resetreas.write(|w| w.dog().detected());
+        }
+        dog
+    }
+
+    /// Starts the watchdog; if the watchdog is not fed (see `RunningWatchdog::feed`) at least once
+    /// every `timeout` the system will be reset
+    ///
+    /// The watchdog is paused while the processor is halted by the debugger
+    ///
+    /// NOTE the watchdog keeps running across soft resets (e.g. the ones performed by `probe-run`).
+    /// If the watchdog was already running then it can't be reconfigured and `timeout` is ignored
+    pub fn start(self, timeout: Duration) -> RunningWatchdog {
+        let wdt = self.wdt;
+
+        if wdt.runstatus.read().runstatus().is_running() {
+            log::warn!("WDT was already running; its timeout can't be changed");
+        } else {
+            let ticks = timeout.as_secs() * TICKS_IN_ONE_SEC
+                + u64::from(timeout.subsec_nanos()) * TICKS_IN_ONE_SEC / 1_000_000_000;
+            let ticks = ticks.max(MIN_TICKS).min(u64::from(u32::MAX)) as u32;
+
+            wdt.config.write(|w| {
+                w.sleep().run();
+                w.halt().pause()
+            });
+            wdt.crv.write(|w| unsafe { w.crv().bits(ticks) });
+            wdt.rren.write(|w| w.rr0().enabled());
+            wdt.tasks_start.write(|w| w.tasks_start().set_bit());
+
+            log::debug!("WDT started with a timeout of {:?}", timeout);
+        }
+
+        RunningWatchdog { wdt }
+    }
+}
+
+impl RunningWatchdog {
+    /// Feeds the watchdog, restarting its countdown
+    pub fn feed(&mut self) {
+        // writing the magic "reload" value into the RR register reloads the counter
+        self.wdt.rr[0].write(|w| w.rr().reload());
+    }
+}